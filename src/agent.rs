@@ -0,0 +1,248 @@
+#![allow(dead_code)]
+
+//! Players that pick turns, and the loop that plays a game between them.
+//!
+//! Anything that can choose a turn can take part in a game: a person at the
+//! terminal, a computer player or a script in a test.
+
+use crate::game::{GameStatus, Quoridor, Turn};
+use crate::illegal_move::IllegalMove;
+use std::collections::VecDeque;
+
+/// What a player does on their turn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Play(Turn),
+    /// Take back the last turn, and the computer players' turns before it
+    Undo,
+    /// Offer a draw, or accept one that is on the table
+    OfferDraw,
+    Resign,
+}
+
+pub trait Agent {
+    /// Picks a turn for the current player. Only asked when there is one.
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn;
+
+    /// Picks what to do on the current player's turn. Playing a turn is
+    /// all most agents do, people may want to undo, offer a draw or resign.
+    fn choose_action(&mut self, game: &Quoridor) -> Action {
+        return Action::Play(self.choose_turn(game));
+    }
+
+    /// Told when the game refuses a turn, before being asked again.
+    /// Only people should get this wrong, so by default it panics.
+    fn refused(&mut self, _game: &Quoridor, turn: Turn, reason: IllegalMove) {
+        panic!("Agent chose an illegal turn {:?}: {}", turn, reason);
+    }
+
+    /// Whether a person is choosing. Undo only stops at their turns.
+    fn is_interactive(&self) -> bool {
+        return false;
+    }
+}
+
+/// Plays the game out with one agent for each player, in turn order.
+///
+/// `before_turn` is called with the game before each player acts,
+/// for saving or showing the game.
+pub fn play_game<F>(
+    game: &mut Quoridor,
+    agents: &mut [Box<dyn Agent>],
+    mut before_turn: F,
+) -> GameStatus
+where
+    F: FnMut(&Quoridor),
+{
+    if agents.len() as i32 != game.number_of_players() {
+        panic!("{} agents for {} players", agents.len(), game.number_of_players());
+    }
+    while !game.is_over() {
+        before_turn(game);
+        let player = game.current_player();
+        let agent = &mut agents[player as usize];
+        match agent.choose_action(game) {
+            Action::Play(turn) => {
+                if let Err(reason) = game.play(turn) {
+                    agent.refused(game, turn, reason);
+                }
+            }
+            Action::Undo => {
+                // Computer players' turns are taken back as well, they would
+                // only play them again
+                game.undo();
+                while !agents[game.current_player() as usize].is_interactive()
+                    && game.undo().is_some()
+                {}
+            }
+            Action::OfferDraw => {
+                game.offer_draw(player).ok();
+            }
+            Action::Resign => {
+                game.resign(player).ok();
+            }
+        }
+    }
+    return game.status();
+}
+
+/// Works through a list of actions, then resigns. For tests and scripts.
+pub struct Scripted {
+    actions: VecDeque<Action>,
+}
+
+impl Scripted {
+    pub fn new(actions: Vec<Action>) -> Scripted {
+        return Scripted {
+            actions: actions.into_iter().collect(),
+        };
+    }
+
+    /// Plays the turns in order.
+    pub fn turns(turns: Vec<Turn>) -> Scripted {
+        return Scripted::new(turns.into_iter().map(Action::Play).collect());
+    }
+}
+
+impl Agent for Scripted {
+    fn choose_turn(&mut self, _game: &Quoridor) -> Turn {
+        while let Some(action) = self.actions.pop_front() {
+            if let Action::Play(turn) = action {
+                return turn;
+            }
+        }
+        panic!("Script has no turns left");
+    }
+
+    fn choose_action(&mut self, _game: &Quoridor) -> Action {
+        return self.actions.pop_front().unwrap_or(Action::Resign);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::Direction::*;
+
+    /// Player 1 walks straight up while player 2 steps aside.
+    fn race() -> Vec<Box<dyn Agent>> {
+        return vec![
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Up); 8])),
+            Box::new(Scripted::turns(
+                [Left, Right].iter().cycle().take(7).map(|side| Turn::MovePawn(*side)).collect(),
+            )),
+        ];
+    }
+
+    #[test]
+    fn scripted_game() {
+        let mut game = Quoridor::new_two_player();
+        let mut turns_seen = 0;
+        let status = play_game(&mut game, &mut race(), |_| turns_seen += 1);
+        assert_eq!(GameStatus::Won { player: 0 }, status);
+        assert_eq!(15, turns_seen);
+        assert_eq!(15, game.history().len());
+    }
+
+    #[test]
+    fn running_out_of_script_resigns() {
+        let mut game = Quoridor::new_two_player();
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Up)])),
+            Box::new(Scripted::new(vec![Action::Play(Turn::MovePawn(Down)), Action::OfferDraw])),
+        ];
+        let status = play_game(&mut game, &mut agents, |_| ());
+        assert_eq!(GameStatus::Resigned { player: 0 }, status);
+        assert_eq!(2, game.history().len());
+    }
+
+    #[test]
+    fn draw_by_agreement() {
+        let mut game = Quoridor::new_two_player();
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(Scripted::new(vec![Action::OfferDraw, Action::Play(Turn::MovePawn(Up))])),
+            Box::new(Scripted::new(vec![Action::OfferDraw])),
+        ];
+        let status = play_game(&mut game, &mut agents, |_| ());
+        assert_eq!(GameStatus::DrawAgreed, status);
+        assert_eq!(1, game.history().len());
+    }
+
+    struct Interactive(Scripted);
+
+    impl Agent for Interactive {
+        fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+            return self.0.choose_turn(game);
+        }
+
+        fn choose_action(&mut self, game: &Quoridor) -> Action {
+            return self.0.choose_action(game);
+        }
+
+        fn refused(&mut self, _game: &Quoridor, _turn: Turn, _reason: IllegalMove) {}
+
+        fn is_interactive(&self) -> bool {
+            return true;
+        }
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_person() {
+        let mut game = Quoridor::new_two_player();
+        let person = Interactive(Scripted::new(vec![
+            Action::Play(Turn::MovePawn(Up)),
+            Action::Undo,
+            Action::Play(Turn::MovePawn(Left)),
+            Action::Resign,
+        ]));
+        let bot = Scripted::turns(vec![Turn::MovePawn(Down), Turn::MovePawn(Left)]);
+        let mut agents: Vec<Box<dyn Agent>> = vec![Box::new(person), Box::new(bot)];
+        play_game(&mut game, &mut agents, |_| ());
+        assert_eq!(&[Turn::MovePawn(Left), Turn::MovePawn(Left)], game.history());
+    }
+
+    #[test]
+    fn people_are_asked_again_after_illegal_turns() {
+        let mut game = Quoridor::new_two_player();
+        let person = Interactive(Scripted::new(vec![
+            Action::Play(Turn::MovePawn(Down)),
+            Action::Play(Turn::MovePawn(Up)),
+            Action::Resign,
+        ]));
+        let mut agents: Vec<Box<dyn Agent>> =
+            vec![Box::new(person), Box::new(Scripted::turns(vec![Turn::MovePawn(Down)]))];
+        play_game(&mut game, &mut agents, |_| ());
+        assert_eq!(&[Turn::MovePawn(Up), Turn::MovePawn(Down)], game.history());
+    }
+
+    #[test]
+    fn boxed_in_players_pass() {
+        // Player 1 on a1 is walled in above, and can't jump player 2 on b1
+        // because player 4 is behind it
+        let position = "9x9 a1,b1,e9,c1 a1h 0,5,5,5 1";
+        let mut game = Quoridor::from_position_string(position).unwrap();
+        assert_eq!(vec![Turn::Pass], game.legal_turns().collect::<Vec<Turn>>());
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(Scripted::turns(vec![Turn::Pass, Turn::MovePawn(Right)])),
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Right)])),
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Down)])),
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Up)])),
+        ];
+        // Once player 2 jumps away player 1 can move again
+        let status = play_game(&mut game, &mut agents, |_| ());
+        assert_eq!(GameStatus::Resigned { player: 1 }, status);
+        assert_eq!(5, game.history().len());
+        assert_eq!(Turn::MovePawn(Right), game.history()[4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "illegal turn")]
+    fn bots_must_not_choose_illegal_turns() {
+        let mut game = Quoridor::new_two_player();
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(Scripted::turns(vec![Turn::MovePawn(Down)])),
+            Box::new(Scripted::turns(vec![])),
+        ];
+        play_game(&mut game, &mut agents, |_| ());
+    }
+}
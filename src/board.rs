@@ -1,21 +1,37 @@
 #![allow(dead_code)]
 
+use crate::goal::{Edge, Goal};
+use crate::illegal_move::IllegalMove;
+use crate::zobrist;
 
 pub type Point = (i32, i32);
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Orientation {
     Vertical,
     Horizontal,
 }
 
+/// Serialized as `{"Wall": [[4, 2], "Horizontal"]}`.
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Wall {
     Wall(Point, Orientation),
-    None,
+}
+
+/// Why a wall can't go where it was asked to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WallConflict {
+    OffBoard,
+    /// Shares part of its length with a wall of the same orientation
+    Overlaps,
+    /// Crosses a wall of the other orientation at its centre
+    Crosses,
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up,
     Right,
@@ -23,6 +39,23 @@ pub enum Direction {
     Left,
 }
 
+impl Direction {
+    /// The direction pointing the other way.
+    pub fn opposite(self) -> Direction {
+        return match self {
+            Up => Down,
+            Right => Left,
+            Down => Up,
+            Left => Right,
+        };
+    }
+
+    /// True if the two directions are at right angles to each other.
+    pub fn is_perpendicular(self, other: Direction) -> bool {
+        return self != other && self != other.opposite();
+    }
+}
+
 pub fn shift((x, y): &Point, direction: Direction) -> Point {
     return match direction {
         Up => (*x, *y + 1),
@@ -32,22 +65,65 @@ pub fn shift((x, y): &Point, direction: Direction) -> Point {
     };
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Serialized as an object with `width`, `height`, `pawns`, `goals` and
+/// `walls`. All four pawns are written, `[-1, -1]` for those not in play.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "BoardData")
+)]
 pub struct Board {
     width: i32,
     height: i32,
     pawns: [Point; 4],
-    walls: [Wall; 20],
+    /// One goal for each pawn that is in play
+    goals: Vec<Goal>,
+    /// Walls in the order they were placed
+    walls: Vec<Wall>,
+    /// Zobrist hash of the pawns and walls, kept up to date as they change
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hash: u64,
+}
+
+/// A `Board` as it is read in, before its hash is worked out.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BoardData {
+    width: i32,
+    height: i32,
+    pawns: [Point; 4],
+    goals: Vec<Goal>,
+    walls: Vec<Wall>,
+}
+
+#[cfg(feature = "serde")]
+impl From<BoardData> for Board {
+    fn from(data: BoardData) -> Board {
+        let mut board = Board::create().set_width(data.width).set_height(data.height);
+        for (pawn, location) in data.pawns.iter().enumerate() {
+            board = board.set_pawn(pawn as i8, *location);
+        }
+        for Wall::Wall(location, orientation) in data.walls {
+            board.walls.push(Wall::Wall(location, orientation));
+            board.hash ^= zobrist::wall(location, orientation);
+        }
+        board.goals = data.goals;
+        return board;
+    }
 }
 
 use Direction::*;
 impl Board {
     pub fn create() -> Board {
+        let pawns = [(-1, -1); 4];
         return Board {
             width: 0,
             height: 0,
-            pawns: [(-1, -1); 4],
-            walls: [Wall::None; 20],
+            hash: (0..4).fold(0, |hash, pawn| hash ^ zobrist::pawn(pawn, pawns[pawn as usize])),
+            pawns,
+            goals: Vec::new(),
+            walls: Vec::new(),
         };
     }
 
@@ -55,8 +131,38 @@ impl Board {
         return Board::create()
             .set_height(9)
             .set_width(9)
-            .set_pawn(0, (4, 0))
-            .set_pawn(1, (4, 8));
+            .add_player((4, 0), Goal::Edge(Edge::Top))
+            .add_player((4, 8), Goal::Edge(Edge::Bottom));
+    }
+
+    /// A 9x9 board with a pawn in the middle of each edge.
+    /// Players are numbered clockwise starting from the bottom.
+    pub fn create_four_player() -> Board {
+        return Board::create()
+            .set_height(9)
+            .set_width(9)
+            .add_player((4, 0), Goal::Edge(Edge::Top))
+            .add_player((0, 4), Goal::Edge(Edge::Right))
+            .add_player((4, 8), Goal::Edge(Edge::Bottom))
+            .add_player((8, 4), Goal::Edge(Edge::Left));
+    }
+
+    /// Puts the next player's pawn on the board along with where it has to get to.
+    pub fn add_player(mut self, location: Point, goal: Goal) -> Board {
+        let pawn = self.goals.len();
+        if pawn >= self.pawns.len() {
+            panic!("Too many players");
+        }
+        self.goals.push(goal);
+        return self.set_pawn(pawn as i8, location);
+    }
+
+    pub fn get_players(&self) -> i8 {
+        return self.goals.len() as i8;
+    }
+
+    pub fn get_goal(&self, pawn: i8) -> Option<&Goal> {
+        return self.goals.get(pawn as usize);
     }
 
     pub fn set_width(mut self, width: i32) -> Board {
@@ -77,24 +183,120 @@ impl Board {
         return self.height;
     }
 
-    fn set_pawn(mut self, pawn: i8, location: Point) -> Board {
+    /// Puts a pawn on a square without checking how it got there.
+    pub fn set_pawn(mut self, pawn: i8, location: Point) -> Board {
+        self.hash ^= zobrist::pawn(pawn, self.pawns[pawn as usize]) ^ zobrist::pawn(pawn, location);
         self.pawns[pawn as usize] = location;
         return self;
     }
 
     pub fn get_pawn(&self, pawn: i8) -> Option<Point> {
-        if pawn >= 0 && pawn < 4 {
+        if (0..4).contains(&pawn) {
             return Some(self.pawns[pawn as usize]);
         }
         return None;
     }
 
-    pub fn pawn_can_move(self, pawn_index: i8, direction: Direction) -> bool {
-        let point = self.pawns[pawn_index as usize];
-        return self.can_move_from(point, direction);
+    /// True if `location` satisfies the pawn's goal.
+    /// Pawns that are not in play have no goal.
+    pub fn is_goal(&self, pawn: i8, location: Point) -> bool {
+        return match self.get_goal(pawn) {
+            Some(goal) => goal.contains(location, self.width, self.height),
+            None => false,
+        };
+    }
+
+    pub fn pawn_can_move(&self, pawn_index: i8, direction: Direction) -> bool {
+        return self.pawn_destination(pawn_index, direction).is_some();
+    }
+
+    pub fn pawn_can_jump(&self, pawn_index: i8, direction: Direction, side: Direction) -> bool {
+        return self.jump_destination(pawn_index, direction, side).is_some();
+    }
+
+    /// Where a pawn lands when moving in `direction`.
+    ///
+    /// If another pawn is in the way the pawn jumps straight over it,
+    /// as long as there is no wall, board edge or pawn behind it.
+    pub fn pawn_destination(&self, pawn_index: i8, direction: Direction) -> Option<Point> {
+        return self.try_pawn_destination(pawn_index, direction).ok();
+    }
+
+    /// Same as `pawn_destination` but explains why the pawn can't move.
+    pub fn try_pawn_destination(
+        &self,
+        pawn_index: i8,
+        direction: Direction,
+    ) -> Result<Point, IllegalMove> {
+        let from = self.pawns[pawn_index as usize];
+        let one_step = self.step(from, direction)?;
+        if !self.is_pawn(&one_step) {
+            return Ok(one_step);
+        }
+
+        let two_steps = self.step(one_step, direction)?;
+        if self.is_pawn(&two_steps) {
+            return Err(IllegalMove::BlockedByPawn);
+        }
+        return Ok(two_steps);
+    }
+
+    /// Where a pawn lands when it jumps diagonally: towards an adjacent pawn
+    /// in `direction` then to the `side` of it.
+    ///
+    /// This is only allowed when the straight jump is blocked by a wall,
+    /// the edge of the board or another pawn.
+    pub fn jump_destination(
+        &self,
+        pawn_index: i8,
+        direction: Direction,
+        side: Direction,
+    ) -> Option<Point> {
+        return self.try_jump_destination(pawn_index, direction, side).ok();
+    }
+
+    /// Same as `jump_destination` but explains why the pawn can't jump.
+    pub fn try_jump_destination(
+        &self,
+        pawn_index: i8,
+        direction: Direction,
+        side: Direction,
+    ) -> Result<Point, IllegalMove> {
+        if !direction.is_perpendicular(side) {
+            return Err(IllegalMove::InvalidJump);
+        }
+
+        let from = self.pawns[pawn_index as usize];
+        let one_step = self.step(from, direction)?;
+        if !self.is_pawn(&one_step) {
+            return Err(IllegalMove::InvalidJump);
+        }
+
+        // Diagonal jumps are only allowed when the straight jump is not
+        if self.try_pawn_destination(pawn_index, direction).is_ok() {
+            return Err(IllegalMove::InvalidJump);
+        }
+
+        let landing = self.step(one_step, side)?;
+        if self.is_pawn(&landing) {
+            return Err(IllegalMove::BlockedByPawn);
+        }
+        return Ok(landing);
+    }
+
+    /// One square on from `from`, unless the edge of the board or a wall is in the way.
+    fn step(&self, from: Point, direction: Direction) -> Result<Point, IllegalMove> {
+        let (x, y) = shift(&from, direction);
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return Err(IllegalMove::OffBoard);
+        }
+        if !self.can_move_from(from, direction) {
+            return Err(IllegalMove::BlockedByWall);
+        }
+        return Ok((x, y));
     }
 
-    pub fn can_move_from(self, (x, y): Point, direction: Direction) -> bool {
+    pub fn can_move_from(&self, (x, y): Point, direction: Direction) -> bool {
         // Check to see if pawn will move off board
         let (new_x, new_y) = match direction {
             Up => (x, y + 1),
@@ -135,108 +337,170 @@ impl Board {
             || self.has_wall(wall_location2, orientation));
     }
 
+    /// Moves a pawn one step, or jumps it straight over an adjacent pawn.
+    /// The board is returned unchanged if the move is not allowed.
     pub fn move_pawn(self, pawn: i8, direction: Direction) -> Board {
-        let (mut x, mut y) = self.pawns[pawn as usize];
-        match direction {
-            Up => y = y + 1,
-            Down => y = y - 1,
-            Right => x = x + 1,
-            Left => x = x - 1,
+        return match self.pawn_destination(pawn, direction) {
+            Some(location) => self.set_pawn(pawn, location),
+            None => self,
         };
+    }
 
-        x = std::cmp::max(x, 0);
-        x = std::cmp::min(x, self.width - 1);
-        y = std::cmp::max(y, 0);
-        y = std::cmp::min(y, self.height - 1);
-
-        return self.set_pawn(pawn, (x, y));
+    /// Jumps a pawn diagonally, see `jump_destination`.
+    /// The board is returned unchanged if the jump is not allowed.
+    pub fn jump_pawn(self, pawn: i8, direction: Direction, side: Direction) -> Board {
+        return match self.jump_destination(pawn, direction, side) {
+            Some(location) => self.set_pawn(pawn, location),
+            None => self,
+        };
     }
 
-    fn first_empty_wall(self) -> usize {
-        let mut i: usize = 0;
+    /// Checks whether a wall would fit on the board, ignoring pawns.
+    ///
+    /// A wall is two squares long. A horizontal wall at `(x, y)` runs along
+    /// the top of squares `(x, y)` and `(x + 1, y)`, a vertical wall at `(x, y)`
+    /// runs along the right of squares `(x, y)` and `(x, y + 1)`. Either way
+    /// its centre is the top right corner of square `(x, y)`.
+    pub fn wall_conflict(&self, (x, y): Point, orientation: Orientation) -> Option<WallConflict> {
+        // Check that wall is on the board
+        if !(x >= 0 && x < self.width - 1 && y >= 0 && y < self.height - 1) {
+            return Some(WallConflict::OffBoard);
+        }
+
         for wall in self.walls.iter() {
-            if wall == &Wall::None {
-                return i;
+            let Wall::Wall((w_x, w_y), w_orient) = *wall;
+
+            // Walls with the same centre either lie on top of each other or cross
+            if w_x == x && w_y == y {
+                if w_orient == orientation {
+                    return Some(WallConflict::Overlaps);
+                }
+                return Some(WallConflict::Crosses);
+            }
+
+            if w_orient != orientation {
+                continue;
+            }
+
+            // Walls in a line overlap if their centres are one square apart
+            let overlaps = match orientation {
+                Orientation::Horizontal => w_y == y && (w_x - x).abs() == 1,
+                Orientation::Vertical => w_x == x && (w_y - y).abs() == 1,
+            };
+            if overlaps {
+                return Some(WallConflict::Overlaps);
             }
-            i = i + 1;
         }
 
-        return 0;
+        return None;
     }
 
-    pub fn can_place_wall(&self, (x, y): Point, orientation: Orientation) -> bool {
-        // Check that wall is on the board
-        if !(x >= 0 && x < self.width-1 && y >= 0 && y < self.height-1) {
+    pub fn can_place_wall(&self, location: Point, orientation: Orientation) -> bool {
+        if self.wall_conflict(location, orientation).is_some() {
             return false;
         }
 
-        // Check if wall would clash with any other wall
-        for wall in self.walls.iter() {
-            match wall {
-                Wall::Wall((w_x, w_y), w_orient) => {
-                    // If they have the same center then they clash
-                    if *w_x == x && *w_y == y {
-                        return false;
-                    }
+        return self.sealed_off_pawn(location, orientation).is_none();
+    }
 
-                    // If they do not have the same center
-                    // Then having different orientations
-                    // means that they can't be interfering
-                    if orientation != *w_orient {
-                        continue;
-                    }
+    /// The first pawn that would have no route to its goal if the wall were placed.
+    /// The wall must fit on the board, see `wall_conflict`.
+    pub fn sealed_off_pawn(&self, location: Point, orientation: Orientation) -> Option<i8> {
+        use crate::path_finder::is_path;
 
-                    if (x - 1 == *w_x || x + 1 == *w_x)
-                        && y == *w_y
-                        && orientation == Orientation::Horizontal
-                    {
-                        return false;
-                    }
+        let board_with_wall = self.clone().place_wall(location, orientation);
 
-                    if (y - 1 == *w_y || y + 1 == *w_y)
-                        && x == *w_x
-                        && orientation == Orientation::Vertical
-                    {
-                        continue;
-                    }
-                }
-                Wall::None => continue,
-            }
-        }
+        return (0..self.get_players()).find(|pawn| {
+            !is_path(&board_with_wall, &self.pawns[*pawn as usize], |point| {
+                self.is_goal(*pawn, point)
+            })
+        });
+    }
 
-        // Check that pawns have a route to target
-        use crate::path_finder::is_path;
+    /// True if a wall at `location` would stand between two neighbouring squares.
+    pub fn wall_blocks(location: Point, orientation: Orientation, a: Point, b: Point) -> bool {
+        let (x, y) = location;
+        let ((low_x, low_y), (high_x, high_y)) = if a < b { (a, b) } else { (b, a) };
+        return match orientation {
+            // Blocks stepping up from (x, y) and (x + 1, y)
+            Orientation::Horizontal => {
+                low_x == high_x && low_y == y && high_y == y + 1 && (low_x == x || low_x == x + 1)
+            }
+            // Blocks stepping right from (x, y) and (x, y + 1)
+            Orientation::Vertical => {
+                low_y == high_y && low_x == x && high_x == x + 1 && (low_y == y || low_y == y + 1)
+            }
+        };
+    }
 
-        let board_with_wall = (*self).place_wall((x, y), orientation);
+    /// Places a wall without checking that pawns can still reach their goals.
+    ///
+    /// Panics if the wall is off the board or collides with another wall,
+    /// use `try_place_wall` to check first.
+    pub fn place_wall(self, location: Point, orientation: Orientation) -> Board {
+        return match self.try_place_wall(location, orientation) {
+            Ok(board) => board,
+            Err(conflict) => panic!(
+                "Can't place {:?} wall at {:?}: {:?}",
+                orientation, location, conflict
+            ),
+        };
+    }
 
-        if !is_path(board_with_wall, &self.pawns[0], |(_, y)| y == 8) {
-            return false;
+    /// Places a wall unless it is off the board or collides with another wall.
+    pub fn try_place_wall(
+        mut self,
+        location: Point,
+        orientation: Orientation,
+    ) -> Result<Board, WallConflict> {
+        if let Some(conflict) = self.wall_conflict(location, orientation) {
+            return Err(conflict);
         }
+        self.walls.push(Wall::Wall(location, orientation));
+        self.hash ^= zobrist::wall(location, orientation);
+        return Ok(self);
+    }
 
-        if !is_path(board_with_wall, &self.pawns[1], |(_, y)| y == 0) {
-            return false;
+    pub fn has_wall(&self, location: Point, orientation: Orientation) -> bool {
+        return self
+            .walls
+            .contains(&Wall::Wall(location, orientation));
+    }
+
+    /// Takes a wall back off the board, if it is there.
+    pub fn remove_wall(mut self, location: Point, orientation: Orientation) -> Board {
+        let wall = Wall::Wall(location, orientation);
+        if self.walls.contains(&wall) {
+            self.walls.retain(|w| *w != wall);
+            self.hash ^= zobrist::wall(location, orientation);
         }
+        return self;
+    }
 
-        return true;
+    pub fn get_walls(&self) -> &[Wall] {
+        return &self.walls;
     }
 
-    pub fn place_wall(mut self, (x, y): Point, orientation: Orientation) -> Board {
-        self.walls[self.first_empty_wall()] = Wall::Wall((x, y), orientation);
-        return self;
+    /// Zobrist hash of where the pawns and walls are. Boards with the same
+    /// pawns and walls have the same hash whatever order the walls went in.
+    pub fn get_hash(&self) -> u64 {
+        return self.hash;
     }
 
-    pub fn has_wall(self, location: Point, orientation: Orientation) -> bool {
-        for wall in self.walls.iter() {
-            match wall {
-                Wall::None => continue,
-                Wall::Wall(wall_location, wall_orientation) => {
-                    if wall_location == &location && wall_orientation == &orientation {
-                        return true;
-                    }
-                }
+    /// The walls of one orientation as a bitmask, bit `y * 8 + x` being the
+    /// wall at (x, y). `None` for boards bigger than 9x9, which have more
+    /// places for walls than fit.
+    pub fn wall_mask(&self, orientation: Orientation) -> Option<u64> {
+        if self.width > 9 || self.height > 9 {
+            return None;
+        }
+        let mut mask = 0;
+        for Wall::Wall((x, y), wall_orientation) in self.walls.iter() {
+            if *wall_orientation == orientation {
+                mask |= 1 << (y * 8 + x);
             }
         }
-        return false;
+        return Some(mask);
     }
 
     pub fn is_pawn(&self, location: &Point) -> bool {
@@ -255,8 +519,8 @@ mod test {
     use super::*;
     #[test]
     fn create_board() {
-        let _: Board = Board::create();
-        assert!(true);
+        let board: Board = Board::create();
+        assert_eq!(0, board.get_width());
     }
 
     #[test]
@@ -296,37 +560,76 @@ mod test {
         assert_eq!((4, 8), board.get_pawn(1).unwrap());
     }
 
+    #[test]
+    fn test_create_four_player_board() {
+        let board = Board::create_four_player();
+        assert_eq!(4, board.get_players());
+        assert_eq!((4, 0), board.get_pawn(0).unwrap());
+        assert_eq!((0, 4), board.get_pawn(1).unwrap());
+        assert_eq!((4, 8), board.get_pawn(2).unwrap());
+        assert_eq!((8, 4), board.get_pawn(3).unwrap());
+    }
+
+    #[test]
+    fn test_goals() {
+        let board = Board::create_default();
+        assert!(board.is_goal(0, (3, 8)));
+        assert!(!board.is_goal(0, (3, 0)));
+        assert!(board.is_goal(1, (3, 0)));
+        assert!(!board.is_goal(1, (8, 3)));
+
+        let board = Board::create_four_player();
+        assert!(board.is_goal(0, (3, 8)));
+        assert!(board.is_goal(1, (8, 3)));
+        assert!(board.is_goal(2, (3, 0)));
+        assert!(board.is_goal(3, (0, 3)));
+        assert!(!board.is_goal(3, (8, 3)));
+    }
+
+    #[test]
+    fn cant_block_side_player() {
+        let board = Board::create_four_player()
+            .place_wall((0, 2), Orientation::Vertical)
+            .place_wall((0, 4), Orientation::Vertical)
+            .place_wall((0, 6), Orientation::Vertical);
+        assert!(board.can_place_wall((0, 7), Orientation::Horizontal));
+        assert!(board.can_place_wall((0, 1), Orientation::Horizontal));
+
+        let board = board.place_wall((0, 7), Orientation::Horizontal);
+        assert!(!board.can_place_wall((0, 1), Orientation::Horizontal));
+    }
+
     #[test]
     fn test_move_pawn() {
         let starting_board = Board::create_default().set_pawn(0, (5, 4));
-        let mut board = starting_board.move_pawn(0, Down);
+        let mut board = starting_board.clone().move_pawn(0, Down);
 
         assert_eq!((5, 3), board.pawns[0]);
 
-        board = starting_board.move_pawn(0, Up);
+        board = starting_board.clone().move_pawn(0, Up);
         assert_eq!((5, 5), board.pawns[0]);
 
-        board = starting_board.move_pawn(0, Right);
+        board = starting_board.clone().move_pawn(0, Right);
         assert_eq!((6, 4), board.pawns[0]);
 
-        board = starting_board.move_pawn(0, Left);
+        board = starting_board.clone().move_pawn(0, Left);
         assert_eq!((4, 4), board.pawns[0]);
     }
 
     #[test]
     fn test_move_pawn_edge() {
         let mut starting_board = Board::create_default().set_pawn(0, (0, 0));
-        let board = starting_board.move_pawn(0, Left);
+        let board = starting_board.clone().move_pawn(0, Left);
         assert_eq!((0, 0), board.get_pawn(0).unwrap());
-        let board = starting_board.move_pawn(0, Down);
+        let board = starting_board.clone().move_pawn(0, Down);
         assert_eq!((0, 0), board.get_pawn(0).unwrap());
         starting_board = Board::create()
             .set_width(5)
             .set_height(8)
             .set_pawn(0, (4, 7));
-        let board = starting_board.move_pawn(0, Right);
+        let board = starting_board.clone().move_pawn(0, Right);
         assert_eq!((4, 7), board.get_pawn(0).unwrap());
-        let board = starting_board.move_pawn(0, Up);
+        let board = starting_board.clone().move_pawn(0, Up);
         assert_eq!((4, 7), board.get_pawn(0).unwrap());
     }
 
@@ -348,28 +651,28 @@ mod test {
         assert!(start_board.pawn_can_move(0, Down));
         assert!(start_board.pawn_can_move(0, Left));
 
-        let board = start_board.place_wall((5, 5), Orientation::Horizontal);
+        let board = start_board.clone().place_wall((5, 5), Orientation::Horizontal);
         assert!(!board.pawn_can_move(0, Up));
 
-        let board = start_board.place_wall((4, 5), Orientation::Horizontal);
+        let board = start_board.clone().place_wall((4, 5), Orientation::Horizontal);
         assert!(!board.pawn_can_move(0, Up));
 
-        let board = start_board.place_wall((5, 5), Orientation::Vertical);
+        let board = start_board.clone().place_wall((5, 5), Orientation::Vertical);
         assert!(!board.pawn_can_move(0, Right));
 
-        let board = start_board.place_wall((5, 4), Orientation::Vertical);
+        let board = start_board.clone().place_wall((5, 4), Orientation::Vertical);
         assert!(!board.pawn_can_move(0, Right));
 
-        let board = start_board.place_wall((4, 5), Orientation::Vertical);
+        let board = start_board.clone().place_wall((4, 5), Orientation::Vertical);
         assert!(!board.pawn_can_move(0, Left));
 
-        let board = start_board.place_wall((4, 4), Orientation::Vertical);
+        let board = start_board.clone().place_wall((4, 4), Orientation::Vertical);
         assert!(!board.pawn_can_move(0, Left));
 
-        let board = start_board.place_wall((4, 4), Orientation::Horizontal);
+        let board = start_board.clone().place_wall((4, 4), Orientation::Horizontal);
         assert!(!board.pawn_can_move(0, Down));
 
-        let board = start_board.place_wall((5, 4), Orientation::Horizontal);
+        let board = start_board.clone().place_wall((5, 4), Orientation::Horizontal);
         assert!(!board.pawn_can_move(0, Down));
     }
 
@@ -397,4 +700,294 @@ mod test {
         assert!(board.has_wall((0, 0), Orientation::Vertical));
         assert!(board.has_wall((1, 1), Orientation::Vertical));
     }
+
+    #[test]
+    fn pawns_cant_share_a_square() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 3))
+            .set_pawn(1, (4, 4))
+            .place_wall((3, 4), Orientation::Horizontal);
+        assert!(!board.pawn_can_move(0, Up));
+        let board = board.move_pawn(0, Up);
+        assert_eq!((4, 3), board.get_pawn(0).unwrap());
+    }
+
+    #[test]
+    fn pawn_jumps_straight_over_pawn() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 3))
+            .set_pawn(1, (4, 4));
+        assert_eq!(Some((4, 5)), board.pawn_destination(0, Up));
+        assert_eq!(Some((4, 2)), board.pawn_destination(1, Down));
+
+        let board = board.move_pawn(0, Up);
+        assert_eq!((4, 5), board.get_pawn(0).unwrap());
+    }
+
+    #[test]
+    fn no_diagonal_jump_when_straight_jump_possible() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 3))
+            .set_pawn(1, (4, 4));
+        assert!(!board.pawn_can_jump(0, Up, Left));
+        assert!(!board.pawn_can_jump(0, Up, Right));
+    }
+
+    #[test]
+    fn diagonal_jump_when_wall_behind_pawn() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 3))
+            .set_pawn(1, (4, 4))
+            .place_wall((4, 4), Orientation::Horizontal);
+        assert!(!board.pawn_can_move(0, Up));
+        assert_eq!(Some((3, 4)), board.jump_destination(0, Up, Left));
+        assert_eq!(Some((5, 4)), board.jump_destination(0, Up, Right));
+
+        let board = board.jump_pawn(0, Up, Right);
+        assert_eq!((5, 4), board.get_pawn(0).unwrap());
+    }
+
+    #[test]
+    fn diagonal_jump_at_edge_of_board() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 7))
+            .set_pawn(1, (4, 8));
+        assert!(!board.pawn_can_move(0, Up));
+        assert_eq!(Some((3, 8)), board.jump_destination(0, Up, Left));
+        assert_eq!(Some((5, 8)), board.jump_destination(0, Up, Right));
+    }
+
+    #[test]
+    fn diagonal_jump_blocked_by_wall_to_the_side() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 7))
+            .set_pawn(1, (4, 8))
+            .place_wall((3, 7), Orientation::Vertical);
+        assert!(!board.pawn_can_jump(0, Up, Left));
+        assert!(board.pawn_can_jump(0, Up, Right));
+    }
+
+    #[test]
+    fn diagonal_jump_must_turn_sideways() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 7))
+            .set_pawn(1, (4, 8));
+        assert!(!board.pawn_can_jump(0, Up, Up));
+        assert!(!board.pawn_can_jump(0, Up, Down));
+        assert!(!board.pawn_can_jump(0, Left, Up));
+    }
+
+    #[test]
+    fn wall_blocks_matches_movement() {
+        let empty = Board::create_default();
+        for x in 0..8 {
+            for y in 0..8 {
+                for orientation in [Orientation::Horizontal, Orientation::Vertical].iter() {
+                    let board = empty.clone().place_wall((x, y), *orientation);
+                    for a_x in 0..9 {
+                        for a_y in 0..9 {
+                            for direction in [Up, Right, Down, Left].iter() {
+                                let a = (a_x, a_y);
+                                let b = shift(&a, *direction);
+                                if !empty.can_move_from(a, *direction) {
+                                    continue;
+                                }
+                                assert_eq!(
+                                    !board.can_move_from(a, *direction),
+                                    Board::wall_blocks((x, y), *orientation, a, b)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_wall() {
+        let board = Board::create_default()
+            .place_wall((4, 4), Orientation::Horizontal)
+            .place_wall((2, 2), Orientation::Vertical);
+        let board = board.remove_wall((4, 4), Orientation::Horizontal);
+        assert!(!board.has_wall((4, 4), Orientation::Horizontal));
+        assert!(board.has_wall((2, 2), Orientation::Vertical));
+        assert!(board.can_place_wall((4, 4), Orientation::Vertical));
+        assert_eq!(
+            Board::create_default().place_wall((2, 2), Orientation::Vertical),
+            board
+        );
+    }
+
+    #[test]
+    fn wall_storage_grows() {
+        let mut board = Board::create().set_width(11).set_height(11);
+        for x in 0..5 {
+            for y in 0..10 {
+                board = board.place_wall((x * 2, y), Orientation::Horizontal);
+            }
+        }
+        assert_eq!(50, board.get_walls().len());
+        assert!(board.has_wall((0, 0), Orientation::Horizontal));
+        assert!(board.has_wall((8, 9), Orientation::Horizontal));
+    }
+
+    #[test]
+    fn explains_why_pawn_cant_move() {
+        let board = Board::create_default()
+            .set_pawn(0, (4, 7))
+            .set_pawn(1, (4, 8))
+            .place_wall((4, 6), Orientation::Vertical);
+        assert_eq!(Err(IllegalMove::OffBoard), board.try_pawn_destination(0, Up));
+        assert_eq!(Err(IllegalMove::BlockedByWall), board.try_pawn_destination(0, Right));
+        assert_eq!(Ok((3, 7)), board.try_pawn_destination(0, Left));
+        assert_eq!(
+            Err(IllegalMove::InvalidJump),
+            board.try_jump_destination(0, Left, Up)
+        );
+        assert_eq!(
+            Err(IllegalMove::InvalidJump),
+            board.try_jump_destination(0, Up, Down)
+        );
+        assert_eq!(Ok((3, 8)), board.try_jump_destination(0, Up, Left));
+    }
+
+    #[test]
+    fn finds_sealed_off_pawn() {
+        let board = Board::create_default()
+            .place_wall((0, 2), Orientation::Horizontal)
+            .place_wall((2, 2), Orientation::Horizontal)
+            .place_wall((4, 2), Orientation::Horizontal)
+            .place_wall((6, 2), Orientation::Horizontal)
+            .place_wall((7, 2), Orientation::Vertical);
+        assert_eq!(None, board.sealed_off_pawn((7, 0), Orientation::Horizontal));
+        assert_eq!(Some(0), board.sealed_off_pawn((7, 1), Orientation::Horizontal));
+        assert!(!board.can_place_wall((7, 1), Orientation::Horizontal));
+    }
+
+    #[test]
+    fn stacked_vertical_walls_overlap() {
+        let board = Board::create_default().place_wall((3, 3), Orientation::Vertical);
+        assert_eq!(
+            Some(WallConflict::Overlaps),
+            board.wall_conflict((3, 4), Orientation::Vertical)
+        );
+        assert_eq!(
+            Some(WallConflict::Overlaps),
+            board.wall_conflict((3, 2), Orientation::Vertical)
+        );
+        assert!(!board.can_place_wall((3, 4), Orientation::Vertical));
+        assert!(board.can_place_wall((3, 5), Orientation::Vertical));
+    }
+
+    #[test]
+    fn crossing_walls_conflict() {
+        let board = Board::create_default().place_wall((3, 3), Orientation::Vertical);
+        assert_eq!(
+            Some(WallConflict::Crosses),
+            board.wall_conflict((3, 3), Orientation::Horizontal)
+        );
+        assert_eq!(None, board.wall_conflict((3, 4), Orientation::Horizontal));
+        assert_eq!(None, board.wall_conflict((2, 3), Orientation::Horizontal));
+    }
+
+    #[test]
+    fn walls_off_board_conflict() {
+        let board = Board::create_default();
+        for location in [(-1, 0), (0, -1), (8, 0), (0, 8)].iter() {
+            for orientation in [Orientation::Horizontal, Orientation::Vertical].iter() {
+                assert_eq!(
+                    Err(WallConflict::OffBoard),
+                    board.clone().try_place_wall(*location, *orientation)
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn place_wall_panics_on_overlap() {
+        Board::create_default()
+            .place_wall((4, 4), Orientation::Horizontal)
+            .place_wall((5, 4), Orientation::Horizontal);
+    }
+
+    /// The unit lengths of edge a wall covers plus its centre, tagged so
+    /// that horizontal and vertical pieces never compare equal.
+    fn wall_pieces((x, y): Point, orientation: Orientation) -> Vec<(char, Point)> {
+        return match orientation {
+            Orientation::Horizontal => vec![('h', (x, y)), ('h', (x + 1, y)), ('c', (x, y))],
+            Orientation::Vertical => vec![('v', (x, y)), ('v', (x, y + 1)), ('c', (x, y))],
+        };
+    }
+
+    #[test]
+    fn every_pair_of_walls() {
+        let orientations = [Orientation::Horizontal, Orientation::Vertical];
+        let mut walls = Vec::new();
+        for x in 0..8 {
+            for y in 0..8 {
+                for orientation in orientations.iter() {
+                    walls.push(((x, y), *orientation));
+                }
+            }
+        }
+
+        let empty = Board::create().set_width(9).set_height(9);
+        for (first, first_orientation) in walls.iter() {
+            let board = empty.clone().place_wall(*first, *first_orientation);
+            let first_pieces = wall_pieces(*first, *first_orientation);
+            for (second, second_orientation) in walls.iter() {
+                let shared = wall_pieces(*second, *second_orientation)
+                    .iter()
+                    .any(|piece| first_pieces.contains(piece));
+                let expected = if !shared {
+                    None
+                } else if first_orientation == second_orientation {
+                    Some(WallConflict::Overlaps)
+                } else {
+                    Some(WallConflict::Crosses)
+                };
+                assert_eq!(
+                    expected,
+                    board.wall_conflict(*second, *second_orientation),
+                    "{:?} {:?} then {:?} {:?}",
+                    first,
+                    first_orientation,
+                    second,
+                    second_orientation
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn wall_masks() {
+        let board = Board::create_default()
+            .place_wall((0, 0), Orientation::Horizontal)
+            .place_wall((7, 7), Orientation::Horizontal)
+            .place_wall((3, 4), Orientation::Vertical);
+        assert_eq!(Some(1 | 1 << 63), board.wall_mask(Orientation::Horizontal));
+        assert_eq!(Some(1 << 35), board.wall_mask(Orientation::Vertical));
+        let board = Board::create().set_width(10).set_height(9);
+        assert_eq!(None, board.wall_mask(Orientation::Vertical));
+    }
+
+    #[test]
+    fn hash_is_kept_up_to_date() {
+        let start = Board::create_default();
+        let board = start
+            .clone()
+            .move_pawn(0, Up)
+            .place_wall((2, 2), Orientation::Vertical);
+        assert_ne!(start.get_hash(), board.get_hash());
+        let board = board
+            .remove_wall((2, 2), Orientation::Vertical)
+            .remove_wall((2, 2), Orientation::Vertical)
+            .move_pawn(0, Down);
+        assert_eq!(start.get_hash(), board.get_hash());
+        // Same pawns, different players
+        let swapped = start.clone().set_pawn(0, (4, 8)).set_pawn(1, (4, 0));
+        assert_ne!(start.get_hash(), swapped.get_hash());
+    }
 }
@@ -1,65 +1,215 @@
 
 #![allow(dead_code)]
 
-use std::collections::LinkedList;
-
 use crate::board::Board;
 use crate::board::Direction;
+use crate::board::WallConflict;
+use crate::illegal_move::IllegalMove;
 use crate::board::Orientation;
 use crate::board::Point;
-use std::fs::{File};
+use crate::rules::RuleSet;
+use std::fmt;
 
-#[derive(Clone, Copy)]
+/// Serialized as `{"PlaceWall": [[4, 2], "Horizontal"]}`, `{"MovePawn": "Up"}`,
+/// `{"JumpPawn": ["Up", "Left"]}` or `"Pass"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Turn {
     PlaceWall(Point, Orientation),
+    /// Step one square, or jump straight over an adjacent pawn.
     MovePawn(Direction),
+    /// Jump diagonally: towards the pawn in the first direction,
+    /// then sideways in the second.
+    JumpPawn(Direction, Direction),
+    /// Let the next player go. Only allowed when there is nothing else to
+    /// play, with the pawn boxed in by other pawns and no walls to place.
+    Pass,
+}
+/// The size of a position written by `Quoridor::to_bytes`.
+pub const POSITION_BYTES: usize = 28;
+
+/// Where a game has got to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameStatus {
+    InProgress,
+    /// The player reached their goal
+    Won { player: i32 },
+    /// The player gave up
+    Resigned { player: i32 },
+    /// Every player offered a draw
+    DrawAgreed,
+    /// The turn limit in the rules was reached
+    DrawByRule,
 }
+
+/// Serialized as an object with the `rules`, the `board`, `current_player`,
+/// `walls_left`, the `history` and `undone` turns, `draw_offers` and the
+/// `ending` if the game was resigned or drawn by agreement. Reading a game
+/// back checks the board against the rules and replays the history, so
+/// games that couldn't have been played are refused.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "QuoridorData")
+)]
 pub struct Quoridor {
+    rules: RuleSet,
+    board: Board,
+    current_player: i32,
+    walls_left: Vec<i32>,
+    history: Vec<Turn>,
+    /// Turns taken back by `undo`, most recent last
+    undone: Vec<Turn>,
+    /// Players who are currently offering a draw
+    draw_offers: Vec<i32>,
+    /// Set when the game ends by resignation or agreement rather than on the board
+    ending: Option<GameStatus>,
+    /// `position_hash`, kept up to date as turns are played and taken back
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hash: u64,
+}
+
+/// A `Quoridor` as it is read in, before it has been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct QuoridorData {
+    rules: RuleSet,
     board: Board,
     current_player: i32,
-    walls_left: [i32; 2],
-    history: Vec<Turn>
+    walls_left: Vec<i32>,
+    history: Vec<Turn>,
+    undone: Vec<Turn>,
+    draw_offers: Vec<i32>,
+    ending: Option<GameStatus>,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<QuoridorData> for Quoridor {
+    type Error = String;
+
+    fn try_from(data: QuoridorData) -> Result<Quoridor, String> {
+        let mut game = Quoridor {
+            rules: data.rules,
+            board: data.board,
+            current_player: data.current_player,
+            walls_left: data.walls_left,
+            history: data.history,
+            undone: data.undone,
+            draw_offers: data.draw_offers,
+            ending: data.ending,
+            hash: 0,
+        };
+        game.hash = game.full_hash();
+        game.validate_position()?;
+        game.validate_history()?;
+        return Ok(game);
+    }
 }
 
 impl Quoridor {
-    /// Creates a new two player game
-    pub fn new_two_player() -> Quoridor {
-        return Quoridor {
-            board: Board::create_default(),
+    /// Creates a new game set up by the given rules.
+    /// Panics if the rules are not valid.
+    pub fn new(rules: RuleSet) -> Quoridor {
+        let mut game = Quoridor {
+            board: rules.create_board(),
             current_player: 0,
-            walls_left: [10, 10],
-            history: Vec::new()
+            walls_left: vec![rules.get_walls_per_player(); rules.get_players() as usize],
+            history: Vec::new(),
+            undone: Vec::new(),
+            draw_offers: Vec::new(),
+            ending: None,
+            hash: 0,
+            rules,
         };
+        game.hash = game.full_hash();
+        return game;
     }
 
-    fn number_of_players(&self) -> i32 {
-        return 2;
+    /// Creates a new two player game
+    pub fn new_two_player() -> Quoridor {
+        return Quoridor::new(RuleSet::standard_two_player());
+    }
+
+    /// Creates a new four player game, each player starts
+    /// in the middle of an edge with 5 walls.
+    pub fn new_four_player() -> Quoridor {
+        return Quoridor::new(RuleSet::standard_four_player());
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        return &self.rules;
+    }
+
+    pub fn board(&self) -> &Board {
+        return &self.board;
     }
 
-    fn current_player(&self) -> i32 {
+    /// The turns played so far, oldest first.
+    pub fn history(&self) -> &[Turn] {
+        return &self.history;
+    }
+
+    /// Turns taken back by `undo`, the next one `redo` would play last.
+    pub fn undone(&self) -> &[Turn] {
+        return &self.undone;
+    }
+
+    /// Zobrist hash of the position: the board, the walls each player has
+    /// left and whose turn it is. Stable between runs, so it can be stored.
+    pub fn position_hash(&self) -> u64 {
+        return self.hash;
+    }
+
+    /// Works out `position_hash` from scratch, for setting up a game.
+    fn full_hash(&self) -> u64 {
+        use crate::zobrist;
+        let mut hash = self.board.get_hash() ^ zobrist::side_to_move(self.current_player);
+        for (player, walls) in self.walls_left.iter().enumerate() {
+            hash ^= zobrist::walls_left(player as i32, *walls);
+        }
+        return hash;
+    }
+
+    /// The part of `position_hash` a turn by `player` can change: the board,
+    /// whose turn it is and the walls the player has left. Xoring it in
+    /// before and after a turn updates the hash.
+    fn turn_keys(&self, player: i32) -> u64 {
+        use crate::zobrist;
+        return self.board.get_hash()
+            ^ zobrist::side_to_move(self.current_player)
+            ^ zobrist::walls_left(player, self.walls_left[player as usize]);
+    }
+
+    pub fn number_of_players(&self) -> i32 {
+        return self.board.get_players() as i32;
+    }
+
+    pub fn current_player(&self) -> i32 {
         return self.current_player;
     }
 
     fn next_turn(&mut self) {
-        self.current_player = (self.current_player + 1) % 2;
+        self.current_player = (self.current_player + 1) % self.number_of_players();
     }
 
     pub fn walls_left(&self, player: i32) -> i32 {
-        if player < 0 || player > 1 {
+        if !(0..self.number_of_players()).contains(&player) {
             panic!("Invalid player index")
         }
         return self.walls_left[player as usize];
     }
 
     fn place_wall(&mut self, location: Point, orientation: Orientation) {
-        self.walls_left[self.current_player() as usize] = self.walls_left[self.current_player() as usize] - 1;
-        self.board = self.board.place_wall(location, orientation);
+        self.walls_left[self.current_player as usize] -= 1;
+        self.board = self.board.clone().place_wall(location, orientation);
         self.next_turn();
 
     }
 
     fn pawn(&self, player: i32) -> Point {
-        if player < 0 || player > 1 {
+        if !(0..self.number_of_players()).contains(&player) {
             panic!("Incorrect player index");
         }
         return self.board.get_pawn(player as i8).unwrap();
@@ -70,76 +220,778 @@ impl Quoridor {
     }
 
     fn move_pawn(&mut self, direction: Direction) {
-        self.board = self.board.move_pawn(self.current_player as i8, direction);
+        self.board = self.board.clone().move_pawn(self.current_player as i8, direction);
+        self.next_turn();
+    }
+
+    fn jump_pawn(&mut self, direction: Direction, side: Direction) {
+        self.board = self.board.clone().jump_pawn(self.current_player as i8, direction, side);
         self.next_turn();
     }
 
     pub fn has_won(&self) -> bool {
-        println!("0: {} ", self.board.get_pawn(0).unwrap().1);
-        println!("1: {} ", self.board.get_pawn(1).unwrap().1);
-        return self.board.get_pawn(0).unwrap().1 == 8 || self.board.get_pawn(1).unwrap().1 == 0;
+        return self.winner().is_some();
     }
 
-    pub fn play(&mut self, turn: Turn) {
-        self.history.push(turn);
+    /// The first player found standing on their goal edge.
+    pub fn winner(&self) -> Option<i32> {
+        return (0..self.number_of_players())
+            .find(|player| self.board.is_goal(*player as i8, self.pawn(*player)));
+    }
+
+    pub fn status(&self) -> GameStatus {
+        if let Some(ending) = self.ending {
+            return ending;
+        }
+        if let Some(player) = self.winner() {
+            return GameStatus::Won { player };
+        }
+        if let Some(limit) = self.rules.get_turn_limit() {
+            if self.history.len() as i32 >= limit {
+                return GameStatus::DrawByRule;
+            }
+        }
+        return GameStatus::InProgress;
+    }
+
+    pub fn is_over(&self) -> bool {
+        return self.status() != GameStatus::InProgress;
+    }
+
+    /// Ends the game with `player` giving up, whoever's turn it is.
+    pub fn resign(&mut self, player: i32) -> Result<GameStatus, IllegalMove> {
+        if !(0..self.number_of_players()).contains(&player) {
+            panic!("Invalid player index")
+        }
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        self.ending = Some(GameStatus::Resigned { player });
+        return Ok(self.status());
+    }
+
+    /// Offers, or accepts, a draw on behalf of `player`.
+    ///
+    /// The game is drawn once every player is offering. An offer stands
+    /// until another player plays a turn instead of accepting it.
+    pub fn offer_draw(&mut self, player: i32) -> Result<GameStatus, IllegalMove> {
+        if !(0..self.number_of_players()).contains(&player) {
+            panic!("Invalid player index")
+        }
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.draw_offers.contains(&player) {
+            self.draw_offers.push(player);
+        }
+        if self.draw_offers.len() as i32 == self.number_of_players() {
+            self.ending = Some(GameStatus::DrawAgreed);
+        }
+        return Ok(self.status());
+    }
+
+    pub fn draw_offers(&self) -> &[i32] {
+        return &self.draw_offers;
+    }
+
+    /// Plays a turn for the current player.
+    ///
+    /// Illegal turns are refused and leave the game untouched,
+    /// otherwise the turn is recorded in the history.
+    pub fn play(&mut self, turn: Turn) -> Result<GameStatus, IllegalMove> {
+        self.validate(turn)?;
+        self.apply(turn);
+        self.undone.clear();
+        return Ok(self.status());
+    }
+
+    fn apply(&mut self, turn: Turn) {
+        // Playing on turns down everyone else's offer of a draw
+        let player = self.current_player;
+        self.draw_offers.retain(|offer| *offer == player);
+        let before = self.turn_keys(player);
         match turn {
             Turn::MovePawn(direction) => {
                 self.move_pawn(direction);
             }
+            Turn::JumpPawn(direction, side) => {
+                self.jump_pawn(direction, side);
+            }
             Turn::PlaceWall(location, orientation) => {
                 self.place_wall(location, orientation);
             }
+            Turn::Pass => self.next_turn(),
         };
+        self.hash ^= before ^ self.turn_keys(player);
+        self.history.push(turn);
     }
 
+    /// Takes back the last turn, putting the board, walls
+    /// and current player back to how they were.
+    ///
+    /// A game that was resigned or drawn by agreement has ended for good
+    /// and can't be undone.
+    pub fn undo(&mut self) -> Option<Turn> {
+        use crate::board::shift;
+        if self.ending.is_some() {
+            return None;
+        }
+        let turn = self.history.pop()?;
+        let players = self.number_of_players();
+        let player = (self.current_player + players - 1) % players;
+        let before = self.turn_keys(player);
+        let landed = self.pawn(player);
+        let from = match turn {
+            Turn::PlaceWall(location, orientation) => {
+                self.board = self.board.clone().remove_wall(location, orientation);
+                self.walls_left[player as usize] += 1;
+                landed
+            }
+            Turn::MovePawn(direction) => {
+                // If there is a pawn behind us we must have jumped over it
+                let back = shift(&landed, direction.opposite());
+                if self.pawn_at(back) {
+                    shift(&back, direction.opposite())
+                } else {
+                    back
+                }
+            }
+            Turn::JumpPawn(direction, side) => {
+                shift(&shift(&landed, side.opposite()), direction.opposite())
+            }
+            Turn::Pass => landed,
+        };
+        self.board = self.board.clone().set_pawn(player as i8, from);
+        self.current_player = player;
+        self.hash ^= before ^ self.turn_keys(player);
+        self.draw_offers.clear();
+        self.undone.push(turn);
+        return Some(turn);
+    }
+
+    /// Plays the last turn taken back by `undo` again.
+    pub fn redo(&mut self) -> Option<Turn> {
+        let turn = self.undone.pop()?;
+        if self.validate(turn).is_err() {
+            self.undone.push(turn);
+            return None;
+        }
+        self.apply(turn);
+        return Some(turn);
+    }
+
+    /// The history in the save format read by `save::read_history`.
     pub fn history_to_str(&self) -> String{
-        let mut result: String = String::new();
+        let mut result: String = format!("Players-{}\n", self.number_of_players());
         for turn in self.history.iter() {
                 match turn {
                 Turn::MovePawn(dir) => {
                     result.push_str(format!("M-{:?}\n", dir).as_str());
                 }
+                Turn::JumpPawn(dir, side) => {
+                    result.push_str(format!("J-{:?}-{:?}\n", dir, side).as_str());
+                }
                 Turn::PlaceWall((x,y), ori) => {
                     result.push_str(format!("P-{},{}-{:?}\n", x, y, ori).as_str())
                 }
+                Turn::Pass => result.push_str("Pass\n"),
             }
         }
         return result;
     }
 
+    /// A copy of the game taken back to before the first turn, with every
+    /// turn ready to `redo`. Resignations and agreed draws are left out so
+    /// the whole game can be replayed.
+    pub fn rewound(&self) -> Quoridor {
+        let mut replay = self.clone();
+        replay.ending = None;
+        replay.undone.clear();
+        while replay.undo().is_some() {}
+        return replay;
+    }
+
+    /// The history written in standard notation, one entry per turn.
+    pub fn history_to_notation(&self) -> Vec<String> {
+        let mut replay = self.rewound();
+
+        let mut result = Vec::new();
+        for turn in self.history.iter() {
+            result.push(turn.to_notation(&replay).unwrap());
+            replay.redo();
+        }
+        return result;
+    }
+
+    /// The position as a single line, for bug reports and test fixtures.
+    ///
+    /// The fields are the board size, each pawn, the walls or `-` for none,
+    /// the walls each player has left and the player to move counting from 1:
+    /// `9x9 e2,e8 e3h,d5v 9,9 1`. Rules that differ from the standard ones
+    /// for the board size and players follow as `walls=15` for the walls each
+    /// player started with and `start=a5,i5` for the starting squares. Only
+    /// the board is kept, not the history or the turn limit.
+    pub fn to_position_string(&self) -> String {
+        use crate::board::Wall;
+        use crate::notation::{square_to_str, wall_to_str};
+        let players = 0..self.number_of_players();
+        let pawns: Vec<String> = players
+            .clone()
+            .map(|player| square_to_str(self.pawn(player)))
+            .collect();
+        let mut walls: Vec<(Point, Orientation)> = self
+            .board
+            .get_walls()
+            .iter()
+            .map(|Wall::Wall(location, orientation)| (*location, *orientation))
+            .collect();
+        // Sorted so the same position always gives the same string
+        walls.sort_by_key(|((x, y), orientation)| (*y, *x, *orientation == Orientation::Vertical));
+        let walls: Vec<String> = walls
+            .iter()
+            .map(|(location, orientation)| wall_to_str(*location, *orientation))
+            .collect();
+        let walls_left: Vec<String> = players
+            .map(|player| self.walls_left(player).to_string())
+            .collect();
+        let mut position = format!(
+            "{}x{} {} {} {} {}",
+            self.rules.get_width(),
+            self.rules.get_height(),
+            pawns.join(","),
+            if walls.is_empty() { String::from("-") } else { walls.join(",") },
+            walls_left.join(","),
+            self.current_player + 1
+        );
+
+        let standard = self.standard_rules();
+        if self.rules.get_walls_per_player() != standard.get_walls_per_player() {
+            position.push_str(&format!(" walls={}", self.rules.get_walls_per_player()));
+        }
+        let starting_squares = self.rules.starting_squares();
+        if starting_squares != standard.starting_squares() {
+            let squares: Vec<String> = starting_squares.into_iter().map(square_to_str).collect();
+            position.push_str(&format!(" start={}", squares.join(",")));
+        }
+        return position;
+    }
+
+    /// Sets up a game from a string written by `to_position_string`.
+    ///
+    /// The players' goals are the opposite edge to where they started, and
+    /// each started with the standard number of walls unless the string
+    /// says otherwise. Positions that couldn't come up in a game, like
+    /// overlapping walls or a pawn with no way to its goal, are refused.
+    pub fn from_position_string(text: &str) -> Result<Quoridor, String> {
+        use crate::notation::{parse_square, parse_wall};
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() < 5 {
+            return Err(format!("expected 5 fields but found {}", fields.len()));
+        }
+
+        let (width, height) = fields[0]
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .ok_or(format!("\"{}\" is not a board size", fields[0]))?;
+        let pawns = fields[1]
+            .split(',')
+            .map(|square| parse_square(square).ok_or(format!("\"{}\" is not a square", square)))
+            .collect::<Result<Vec<Point>, String>>()?;
+        let walls = match fields[2] {
+            "-" => Vec::new(),
+            walls => walls
+                .split(',')
+                .map(|wall| parse_wall(wall).ok_or(format!("\"{}\" is not a wall", wall)))
+                .collect::<Result<Vec<(Point, Orientation)>, String>>()?,
+        };
+        let walls_left = fields[3]
+            .split(',')
+            .map(|walls| {
+                walls
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a number of walls", walls))
+            })
+            .collect::<Result<Vec<i32>, String>>()?;
+        let side: i32 = fields[4]
+            .parse()
+            .map_err(|_| format!("\"{}\" is not a player", fields[4]))?;
+
+        let mut rules = Quoridor::position_rules((width, height), pawns.len() as i32);
+        for field in &fields[5..] {
+            rules = match field.split_once('=') {
+                Some(("walls", walls)) => rules.set_walls_per_player(
+                    walls
+                        .parse()
+                        .map_err(|_| format!("\"{}\" is not a number of walls", walls))?,
+                ),
+                Some(("start", squares)) => rules.set_starting_squares(
+                    squares
+                        .split(',')
+                        .map(|square| {
+                            parse_square(square).ok_or(format!("\"{}\" is not a square", square))
+                        })
+                        .collect::<Result<Vec<Point>, String>>()?,
+                ),
+                _ => return Err(format!("\"{}\" is not a rule", field)),
+            };
+        }
+        return Quoridor::from_parts(rules, &pawns, &walls, walls_left, side - 1);
+    }
+
+    /// The rules a position string or bytes stand for when they don't say
+    /// otherwise: the standard rules for that many players on that board.
+    fn position_rules((width, height): (i32, i32), players: i32) -> RuleSet {
+        let rules = match players {
+            4 => RuleSet::standard_four_player(),
+            _ => RuleSet::standard_two_player().set_players(players),
+        };
+        return rules.set_width(width).set_height(height);
+    }
+
+    /// `position_rules` for this game's board and players.
+    fn standard_rules(&self) -> RuleSet {
+        let size = (self.rules.get_width(), self.rules.get_height());
+        return Quoridor::position_rules(size, self.number_of_players());
+    }
+
+    /// Sets up a game in the middle of play, with the goals and walls each
+    /// of the rules for that many players. `side` counts from 0.
+    fn from_parts(
+        rules: RuleSet,
+        pawns: &[Point],
+        walls: &[(Point, Orientation)],
+        walls_left: Vec<i32>,
+        side: i32,
+    ) -> Result<Quoridor, String> {
+        use crate::notation::wall_to_str;
+        let players = pawns.len() as i32;
+        rules.validate()?;
+        if walls_left.len() != pawns.len() {
+            return Err(format!(
+                "walls left for {} players but {} pawns",
+                walls_left.len(),
+                players
+            ));
+        }
+        if !(0..players).contains(&side) {
+            return Err(format!("there is no player {} to move", side + 1));
+        }
+
+        let mut game = Quoridor::new(rules);
+        for (player, square) in pawns.iter().enumerate() {
+            game.board = game.board.clone().set_pawn(player as i8, *square);
+        }
+        for (location, orientation) in walls.iter() {
+            game.board = game
+                .board
+                .clone()
+                .try_place_wall(*location, *orientation)
+                .map_err(|conflict| {
+                    let wall = wall_to_str(*location, *orientation);
+                    format!("wall {} can't go there: {:?}", wall, conflict)
+                })?;
+        }
+        game.walls_left = walls_left;
+        game.current_player = side;
+        game.hash = game.full_hash();
+        game.validate_position()?;
+        return Ok(game);
+    }
+
+    /// The same position played by other rules, like a turn limit the
+    /// position string couldn't hold. Refused once turns have been played,
+    /// or if the position couldn't come up in a game played by `rules`.
+    pub fn with_rules(mut self, rules: RuleSet) -> Result<Quoridor, String> {
+        if !self.history.is_empty() {
+            return Err(String::from("the rules can't change once the game has started"));
+        }
+        self.rules = rules;
+        self.validate_position()?;
+        return Ok(self);
+    }
+
+    /// Writes the position in `POSITION_BYTES` bytes: the width, height and
+    /// number of players, each pawn's square as `y * width + x`, the walls
+    /// each player has left, the player to move and then the horizontal and
+    /// vertical wall masks from `Board::wall_mask` as little endian `u64`s.
+    /// Unused pawn and wall slots are `0xFF`. Like the position string, only
+    /// the board is kept. Boards bigger than 9x9 don't fit, and neither do
+    /// rules other than the standard ones for the board and players.
+    pub fn to_bytes(&self) -> Result<[u8; POSITION_BYTES], String> {
+        let (width, height) = (self.rules.get_width(), self.rules.get_height());
+        let standard = self.standard_rules();
+        if self.rules.get_walls_per_player() != standard.get_walls_per_player() {
+            let walls = self.rules.get_walls_per_player();
+            return Err(format!("{} walls each doesn't fit in bytes", walls));
+        }
+        if self.rules.starting_squares() != standard.starting_squares() {
+            return Err(String::from("starting squares don't fit in bytes"));
+        }
+        let too_big = || format!("a {}x{} board doesn't fit in bytes", width, height);
+        let horizontal = self.board.wall_mask(Orientation::Horizontal).ok_or_else(too_big)?;
+        let vertical = self.board.wall_mask(Orientation::Vertical).ok_or_else(too_big)?;
+
+        let mut bytes = [0xFF; POSITION_BYTES];
+        bytes[0] = width as u8;
+        bytes[1] = height as u8;
+        bytes[2] = self.number_of_players() as u8;
+        for player in 0..self.number_of_players() {
+            let (x, y) = self.pawn(player);
+            bytes[3 + player as usize] = (y * width + x) as u8;
+            if !(0..0xFF).contains(&self.walls_left(player)) {
+                return Err(format!("{} walls left doesn't fit in a byte", self.walls_left(player)));
+            }
+            bytes[7 + player as usize] = self.walls_left(player) as u8;
+        }
+        bytes[11] = self.current_player as u8;
+        bytes[12..20].copy_from_slice(&horizontal.to_le_bytes());
+        bytes[20..28].copy_from_slice(&vertical.to_le_bytes());
+        return Ok(bytes);
+    }
+
+    /// Sets up a game from bytes written by `to_bytes`, refusing
+    /// positions that couldn't come up in a game and unused slots that
+    /// aren't `0xFF`, so each position can only be read from one encoding.
+    pub fn from_bytes(bytes: &[u8; POSITION_BYTES]) -> Result<Quoridor, String> {
+        let (width, height) = (bytes[0] as i32, bytes[1] as i32);
+        if width > 9 || height > 9 {
+            return Err(format!("a {}x{} board doesn't fit in bytes", width, height));
+        }
+        let players = bytes[2] as usize;
+        if !(1..=4).contains(&players) {
+            return Err(format!("{} players is not supported", players));
+        }
+        let mut unused = bytes[3 + players..7].iter().chain(&bytes[7 + players..11]);
+        if unused.any(|byte| *byte != 0xFF) {
+            return Err(String::from("unused player slots have to be 0xFF"));
+        }
+        let pawns: Vec<Point> = bytes[3..3 + players]
+            .iter()
+            .map(|square| (*square as i32 % width.max(1), *square as i32 / width.max(1)))
+            .collect();
+        let walls_left: Vec<i32> = bytes[7..7 + players]
+            .iter()
+            .map(|walls| *walls as i32)
+            .collect();
+
+        let mut walls = Vec::new();
+        let masks = [(12, Orientation::Horizontal), (20, Orientation::Vertical)];
+        for (offset, orientation) in masks.iter() {
+            let mut mask = [0; 8];
+            mask.copy_from_slice(&bytes[*offset..offset + 8]);
+            let mask = u64::from_le_bytes(mask);
+            for bit in 0..64 {
+                if mask & (1 << bit) != 0 {
+                    walls.push(((bit % 8, bit / 8), *orientation));
+                }
+            }
+        }
+        let rules = Quoridor::position_rules((width, height), players as i32);
+        return Quoridor::from_parts(rules, &pawns, &walls, walls_left, bytes[11] as i32);
+    }
+
+    /// Explains what is wrong with the position, if anything. A position is
+    /// only valid if it could come up in a game played by the rules.
+    fn validate_position(&self) -> Result<(), String> {
+        use crate::board::Wall;
+        use crate::notation::{square_to_str, wall_to_str};
+        use crate::path_finder::distance_to_goal;
+        self.rules.validate()?;
+        let players = self.rules.get_players();
+        if self.board.get_players() as i32 != players {
+            return Err(format!("{} pawns for {} players", self.board.get_players(), players));
+        }
+
+        // Set the board up again from the rules so every wall gets checked
+        let mut board = self.rules.create_board();
+        for player in 0..players {
+            let square = self.pawn(player);
+            let (x, y) = square;
+            if x < 0 || y < 0 || x >= board.get_width() || y >= board.get_height() {
+                return Err(format!("pawn {:?} is off the board", square));
+            }
+            if (0..player).any(|other| self.pawn(other) == square) {
+                return Err(format!("two pawns on {}", square_to_str(square)));
+            }
+            board = board.set_pawn(player as i8, square);
+        }
+        for Wall::Wall(location, orientation) in self.board.get_walls() {
+            board = board
+                .try_place_wall(*location, *orientation)
+                .map_err(|conflict| {
+                    let wall = wall_to_str(*location, *orientation);
+                    format!("wall {} can't go there: {:?}", wall, conflict)
+                })?;
+        }
+        if board != self.board {
+            return Err(String::from("the board doesn't match the rules"));
+        }
+        for player in 0..players {
+            if distance_to_goal(&self.board, player as i8).is_none() {
+                return Err(format!("player {} can't reach their goal", player + 1));
+            }
+        }
+
+        let per_player = self.rules.get_walls_per_player();
+        if self.walls_left.len() as i32 != players {
+            return Err(format!("walls left for {} players", self.walls_left.len()));
+        }
+        let out_of_range = |walls: &&i32| !(0..=per_player).contains(*walls);
+        if let Some(walls) = self.walls_left.iter().find(out_of_range) {
+            return Err(format!("{} walls left is not between 0 and {}", walls, per_player));
+        }
+        let used = per_player * players - self.walls_left.iter().sum::<i32>();
+        if self.board.get_walls().len() as i32 > used {
+            return Err(format!(
+                "{} walls on the board but only {} have been used",
+                self.board.get_walls().len(),
+                used
+            ));
+        }
+        if !(0..players).contains(&self.current_player) {
+            return Err(format!("there is no player {} to move", self.current_player + 1));
+        }
+        return Ok(());
+    }
+
+    /// Explains what is wrong with the history, draw offers or ending, if
+    /// anything. The history has to lead to the position on the board and
+    /// the undone turns have to be playable again from there.
+    fn validate_history(&self) -> Result<(), String> {
+        let players = 0..self.number_of_players();
+        for (i, player) in self.draw_offers.iter().enumerate() {
+            if !players.contains(player) || self.draw_offers[..i].contains(player) {
+                return Err(format!("player {} can't offer a draw", player + 1));
+            }
+        }
+        match self.ending {
+            None => (),
+            Some(GameStatus::Resigned { player }) if players.contains(&player) => (),
+            Some(GameStatus::DrawAgreed) if self.draw_offers.len() == players.len() => (),
+            Some(ending) => return Err(format!("the game can't have ended with {:?}", ending)),
+        }
+
+        let mut replay = self.clone();
+        replay.ending = None;
+        replay.undone.clear();
+        for _ in 0..self.history.len() {
+            replay.undo();
+        }
+        replay.undone.clear();
+        replay
+            .validate_position()
+            .map_err(|reason| format!("the history doesn't fit the board, {}", reason))?;
+        for (i, turn) in self.history.iter().enumerate() {
+            replay
+                .play(*turn)
+                .map_err(|reason| format!("turn {} of the history is illegal: {}", i + 1, reason))?;
+        }
+        if replay.board != self.board
+            || replay.walls_left != self.walls_left
+            || replay.current_player != self.current_player
+        {
+            return Err(String::from("the history doesn't lead to the board"));
+        }
+
+        replay.undone = self.undone.clone();
+        for i in 0..self.undone.len() {
+            if replay.redo().is_none() {
+                return Err(format!("undone turn {} can't be played again", i + 1));
+            }
+        }
+        return Ok(());
+    }
+
+    /// The undone turns written in standard notation, in the order `redo`
+    /// would play them.
+    pub fn undone_to_notation(&self) -> Vec<String> {
+        let mut replay = self.clone();
+        replay.ending = None;
+
+        let mut result = Vec::new();
+        while let Some(turn) = replay.undone.last() {
+            result.push(turn.to_notation(&replay).unwrap());
+            replay.redo();
+        }
+        return result;
+    }
+
     pub fn can_move(&self, direction: Direction) -> bool {
-        return self.board.pawn_can_move(self.current_player() as i8, direction);
+        return self.is_valid(Turn::MovePawn(direction));
+    }
+
+    pub fn can_jump(&self, direction: Direction, side: Direction) -> bool {
+        return self.is_valid(Turn::JumpPawn(direction, side));
     }
 
     pub fn can_place_wall(&self, location:Point, orientation: Orientation) -> bool {
-        if self.walls_left[self.current_player() as usize] <= 0 {
-            return false;
-        } else {
-            return self.board.can_place_wall(location, orientation)
+        return self.is_valid(Turn::PlaceWall(location, orientation));
+    }
+
+    /// Every turn the current player could play, pawn moves first then
+    /// walls, or just `Pass` if there are neither.
+    pub fn legal_turns(&self) -> impl Iterator<Item = Turn> + '_ {
+        let pass = std::iter::once(Turn::Pass).filter(move |turn| self.is_valid(*turn));
+        return self.legal_pawn_turns().chain(self.legal_walls()).chain(pass);
+    }
+
+    /// The number of turns `legal_turns` would give. This does the same
+    /// checks as listing them, which for walls only means a full path check
+    /// for those that cut across a pawn's shortest route, see `legal_walls`.
+    pub fn legal_turn_count(&self) -> usize {
+        let count = self.legal_pawn_turns().count() + self.legal_walls().count();
+        if count == 0 && !self.is_over() {
+            return 1;
+        }
+        return count;
+    }
+
+    /// The pawn moves and jumps the current player can make.
+    pub fn legal_pawn_turns(&self) -> impl Iterator<Item = Turn> + '_ {
+        return self.pawn_turns().filter(move |turn| self.is_valid(*turn));
+    }
+
+    /// The square the current player's pawn lands on, if the turn is a legal pawn move.
+    pub fn destination(&self, turn: Turn) -> Option<Point> {
+        let pawn = self.current_player as i8;
+        if self.is_over() {
+            return None;
         }
+        return match turn {
+            Turn::MovePawn(direction) => self.board.pawn_destination(pawn, direction),
+            Turn::JumpPawn(direction, side) => self.board.jump_destination(pawn, direction, side),
+            Turn::PlaceWall(..) | Turn::Pass => None,
+        };
+    }
+
+    /// Every pawn move and jump, legal or not.
+    pub fn pawn_turns(&self) -> impl Iterator<Item = Turn> {
+        use Direction::*;
+        let directions = [Up, Right, Down, Left];
+        let mut turns: Vec<Turn> = directions.iter().map(|d| Turn::MovePawn(*d)).collect();
+        for direction in directions.iter() {
+            for side in directions.iter() {
+                if direction.is_perpendicular(*side) {
+                    turns.push(Turn::JumpPawn(*direction, *side));
+                }
+            }
+        }
+        return turns.into_iter();
+    }
+
+    /// Every wall the current player could place.
+    ///
+    /// Finding a route for every pawn is slow, so each pawn's shortest route
+    /// is found once up front. A wall that doesn't cut any of those routes
+    /// can't seal anyone off, so only the walls that do need checking in full.
+    pub fn legal_walls(&self) -> impl Iterator<Item = Turn> + '_ {
+        use crate::path_finder::path_to_goal;
+        let can_place = !self.is_over() && self.walls_left[self.current_player as usize] > 0;
+        let routes: Vec<Vec<Point>> = if can_place {
+            (0..self.number_of_players())
+                .filter_map(|player| path_to_goal(&self.board, player as i8))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let columns = self.board.get_width() - 1;
+        let rows = self.board.get_height() - 1;
+        let candidates = if can_place { columns * rows * 2 } else { 0 };
+        return (0..candidates)
+            .map(move |i| {
+                let orientation = if i % 2 == 0 {
+                    Orientation::Horizontal
+                } else {
+                    Orientation::Vertical
+                };
+                ((i / 2 % columns, i / 2 / columns), orientation)
+            })
+            .filter(move |(location, orientation)| {
+                if self.board.wall_conflict(*location, *orientation).is_some() {
+                    return false;
+                }
+                let cuts_route = routes.iter().any(|route| {
+                    route
+                        .windows(2)
+                        .any(|step| Board::wall_blocks(*location, *orientation, step[0], step[1]))
+                });
+                return !cuts_route || self.board.sealed_off_pawn(*location, *orientation).is_none();
+            })
+            .map(|(location, orientation)| Turn::PlaceWall(location, orientation));
     }
 
     pub fn is_valid(&self, turn: Turn) -> bool {
+        return self.validate(turn).is_ok();
+    }
+
+    /// Checks that the current player can play `turn`, explaining why not if they can't.
+    pub fn validate(&self, turn: Turn) -> Result<(), IllegalMove> {
+        return self.validate_for(self.current_player(), turn);
+    }
+
+    /// Checks that `player` can play `turn` right now.
+    pub fn validate_for(&self, player: i32, turn: Turn) -> Result<(), IllegalMove> {
         use Turn::*;
-        return match turn {
-            MovePawn(direction) => self.can_move(direction),
-            PlaceWall(location, orientation) => self.can_place_wall(location, orientation)
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
         }
+        if player != self.current_player() {
+            return Err(IllegalMove::NotYourTurn);
+        }
+
+        let pawn = player as i8;
+        return match turn {
+            MovePawn(direction) => self.board.try_pawn_destination(pawn, direction).map(|_| ()),
+            JumpPawn(direction, side) => self
+                .board
+                .try_jump_destination(pawn, direction, side)
+                .map(|_| ()),
+            PlaceWall(location, orientation) => {
+                if self.walls_left[player as usize] <= 0 {
+                    return Err(IllegalMove::NoWallsLeft);
+                }
+                match self.board.wall_conflict(location, orientation) {
+                    Some(WallConflict::OffBoard) => return Err(IllegalMove::OffBoard),
+                    Some(WallConflict::Overlaps) => return Err(IllegalMove::OverlappingWall),
+                    Some(WallConflict::Crosses) => return Err(IllegalMove::CrossingWall),
+                    None => (),
+                }
+                match self.board.sealed_off_pawn(location, orientation) {
+                    Some(sealed) => Err(IllegalMove::WouldSealOff { player: sealed as i32 }),
+                    None => Ok(()),
+                }
+            }
+            Pass => {
+                if self.legal_pawn_turns().next().is_some() || self.legal_walls().next().is_some()
+                {
+                    return Err(IllegalMove::CantPass);
+                }
+                Ok(())
+            }
+        };
     }
 
-    pub fn to_string(&self) -> String {
+}
+
+impl fmt::Display for Quoridor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut result = String::new();
-        result.push_str("\n");
-        result.push_str(format!("Player 1: {} walls left \nPlayer 2: {} walls left\nCurrent Player: {} \n\n", 
-            self.walls_left[0], 
-            self.walls_left[1],
-            self.current_player()+1).as_str());
+        result.push('\n');
+        for player in 0..self.number_of_players() {
+            result.push_str(format!("Player {}: {} walls left \n",
+                player + 1,
+                self.walls_left[player as usize]).as_str());
+        }
+        result.push_str(format!("Current Player: {} \n\n", self.current_player()+1).as_str());
+        // Row labels are right-aligned so tall boards still line up
+        let label_width = self.board.get_height().to_string().len();
+        let margin = " ".repeat(label_width + 1);
         for not_y in 0..self.board.get_height() {
             let y = self.board.get_height() - not_y - 1;
 
             // Add wall row 
-            result.push_str((y+1).to_string().as_str());
-            result.push_str(" ");
+            result.push_str(format!("{:>width$} ", y + 1, width = label_width).as_str());
             for x in 0..self.board.get_width() {
                 if self.board.has_wall((x - 1, y), Orientation::Horizontal)
                     || self.board.has_wall((x, y), Orientation::Horizontal)
@@ -157,12 +1009,15 @@ impl Quoridor {
                 }
             }
 
-            result.push_str("\n");
-                result.push_str("  ");
+            result.push('\n');
+            result.push_str(&margin);
             for x in 0..self.board.get_width() {
                 // Add a squares row
                 if self.pawn_at((x, y)) {
-                    result.push_str("PP")
+                    let player = (0..self.number_of_players())
+                        .find(|player| self.pawn(*player) == (x, y))
+                        .unwrap();
+                    result.push_str(format!("P{}", player + 1).as_str())
                 } else {
                     result.push_str("()");
                 }
@@ -178,15 +1033,19 @@ impl Quoridor {
             }
 
             
-            result.push_str("\n");
+            result.push('\n');
             
         }
 
         //result.push_str("why can;'t I see this?");
 
-        result.push_str("    A   B   C   D   E   F   G   H\n");
+        result.push_str(&margin);
+        for x in 0..self.board.get_width() {
+            result.push_str(format!("{:<4}", (b'A' + x as u8) as char).as_str());
+        }
+        result.push('\n');
 
-        return result;
+        return write!(f, "{}", result);
     }
 }
 
@@ -194,6 +1053,38 @@ impl Quoridor {
 mod tests {
     use super::*;
 
+    #[test]
+    fn display_lines_up_tall_boards() {
+        let rules = RuleSet::standard_two_player().set_width(11).set_height(11);
+        let text = Quoridor::new(rules).to_string();
+        let lines: Vec<&str> = text.lines().skip(5).collect();
+        assert!(lines[0].starts_with("11 "), "{:?}", lines[0]);
+        assert!(lines[20].starts_with(" 1 "), "{:?}", lines[20]);
+        // Every square row, including the pawns on rows 1 and 11, is the same width
+        let squares: Vec<&&str> = lines.iter().skip(1).step_by(2).take(11).collect();
+        assert!(squares.iter().all(|line| line.len() == squares[0].len()));
+        assert!(squares[0].starts_with("   ()"), "{:?}", squares[0]);
+        assert!(lines[22].starts_with("   A   B"), "{:?}", lines[22]);
+
+        let text = Quoridor::new_two_player().to_string();
+        assert!(text.contains("\n9 "));
+        assert!(text.contains("\n  A   B"));
+    }
+
+    #[test]
+    fn games_can_be_searched_on_another_thread() {
+        use crate::goal::Goal;
+        use std::sync::Arc;
+        let board = Board::create_default()
+            .add_player((0, 4), Goal::Predicate(Arc::new(|(x, _)| x == 8)));
+        let game = Quoridor::new_two_player();
+        let count = std::thread::spawn(move || {
+            assert_eq!(3, board.get_players());
+            return game.legal_turn_count();
+        });
+        assert_eq!(131, count.join().unwrap());
+    }
+
     #[test]
     fn test_create_two_player_game() {
         let game = Quoridor::new_two_player();
@@ -205,6 +1096,819 @@ mod tests {
         assert_eq!((4, 8), game.pawn(1));
     }
 
+    #[test]
+    fn test_create_four_player_game() {
+        let game = Quoridor::new_four_player();
+        assert_eq!(4, game.number_of_players());
+        assert_eq!(0, game.current_player());
+        for player in 0..4 {
+            assert_eq!(5, game.walls_left(player));
+        }
+        assert_eq!((4, 0), game.pawn(0));
+        assert_eq!((0, 4), game.pawn(1));
+        assert_eq!((4, 8), game.pawn(2));
+        assert_eq!((8, 4), game.pawn(3));
+    }
+
+    #[test]
+    fn test_four_player_turns_rotate() {
+        let mut game = Quoridor::new_four_player();
+        for player in [1, 2, 3, 0, 1].iter() {
+            game.move_pawn(Direction::Up);
+            assert_eq!(*player, game.current_player());
+        }
+    }
+
+    #[test]
+    fn four_player_side_can_win() {
+        use Direction::*;
+        let mut game = Quoridor::new_four_player();
+        let (shuffle, back) = ([Up, Right, Up], [Down, Left, Down]);
+        for round in 0..7 {
+            let others = if round % 2 == 0 { shuffle } else { back };
+            game.move_pawn(Up);
+            for m in others.iter() {
+                game.move_pawn(*m);
+            }
+            assert!(!game.has_won());
+        }
+        game.move_pawn(Up);
+        assert_eq!(Some(0), game.winner());
+
+        let mut game = Quoridor::new_four_player();
+        let (shuffle, back) = ([Left, Up, Right], [Right, Down, Left]);
+        game.move_pawn(Left);
+        for round in 0..7 {
+            let others = if round % 2 == 0 { shuffle } else { back };
+            game.move_pawn(Right);
+            for m in others.iter() {
+                game.move_pawn(*m);
+            }
+            assert!(!game.has_won());
+        }
+        game.move_pawn(Right);
+        assert_eq!(Some(1), game.winner());
+    }
+
+    #[test]
+    fn test_create_game_from_rules() {
+        let game = Quoridor::new(
+            RuleSet::standard_two_player()
+                .set_width(5)
+                .set_height(5)
+                .set_walls_per_player(3),
+        );
+        assert_eq!(2, game.number_of_players());
+        assert_eq!(3, game.walls_left(0));
+        assert_eq!((2, 0), game.pawn(0));
+        assert_eq!((2, 4), game.pawn(1));
+
+        let game = Quoridor::new(
+            RuleSet::standard_two_player()
+                .set_width(11)
+                .set_height(11)
+                .set_walls_per_player(15),
+        );
+        assert_eq!(15, game.walls_left(1));
+        assert_eq!((5, 10), game.pawn(1));
+    }
+
+    #[test]
+    fn big_board_holds_every_wall() {
+        let mut game = Quoridor::new(
+            RuleSet::standard_two_player()
+                .set_width(11)
+                .set_height(11)
+                .set_walls_per_player(15),
+        );
+        for i in 0..30 {
+            let wall = ((i % 5) * 2, i / 5 + 1);
+            assert!(game.is_valid(Turn::PlaceWall(wall, Orientation::Horizontal)));
+            game.play(Turn::PlaceWall(wall, Orientation::Horizontal)).unwrap();
+        }
+        assert_eq!(0, game.walls_left(0));
+        assert_eq!(0, game.walls_left(1));
+        assert!(game.board.has_wall((0, 1), Orientation::Horizontal));
+    }
+
+    #[test]
+    fn validate_explains_illegal_moves() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        assert_eq!(Ok(()), game.validate(Turn::MovePawn(Up)));
+        assert_eq!(Err(IllegalMove::OffBoard), game.validate(Turn::MovePawn(Down)));
+        assert_eq!(Err(IllegalMove::InvalidJump), game.validate(Turn::JumpPawn(Up, Left)));
+        assert_eq!(
+            Err(IllegalMove::OffBoard),
+            game.validate(Turn::PlaceWall((8, 0), Orientation::Vertical))
+        );
+        assert_eq!(
+            Err(IllegalMove::NotYourTurn),
+            game.validate_for(1, Turn::MovePawn(Down))
+        );
+
+        game.play(Turn::PlaceWall((4, 0), Orientation::Vertical)).unwrap();
+        assert_eq!(Ok(()), game.validate_for(1, Turn::MovePawn(Down)));
+        assert_eq!(
+            Err(IllegalMove::OverlappingWall),
+            game.validate(Turn::PlaceWall((4, 1), Orientation::Vertical))
+        );
+        assert_eq!(
+            Err(IllegalMove::CrossingWall),
+            game.validate(Turn::PlaceWall((4, 0), Orientation::Horizontal))
+        );
+
+        game.play(Turn::PlaceWall((3, 0), Orientation::Horizontal)).unwrap();
+        assert_eq!(Err(IllegalMove::BlockedByWall), game.validate(Turn::MovePawn(Up)));
+        assert_eq!(
+            Err(IllegalMove::WouldSealOff { player: 0 }),
+            game.validate(Turn::PlaceWall((2, 0), Orientation::Vertical))
+        );
+    }
+
+    #[test]
+    fn validate_walls_left_and_game_over() {
+        use Direction::*;
+        let mut game = Quoridor::new(RuleSet::standard_two_player().set_walls_per_player(0));
+        assert_eq!(
+            Err(IllegalMove::NoWallsLeft),
+            game.validate(Turn::PlaceWall((4, 4), Orientation::Vertical))
+        );
+
+        for _ in 0..7 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            let side = if game.pawn(1).0 == 4 { Left } else { Right };
+            game.play(Turn::MovePawn(side)).unwrap();
+        }
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert!(game.has_won());
+        assert_eq!(Err(IllegalMove::GameOver), game.validate(Turn::MovePawn(Left)));
+    }
+
+    #[test]
+    fn play_refuses_illegal_turns() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        assert_eq!(Err(IllegalMove::OffBoard), game.play(Turn::MovePawn(Down)));
+        assert_eq!(0, game.current_player());
+        assert!(game.history.is_empty());
+
+        assert_eq!(Ok(GameStatus::InProgress), game.play(Turn::MovePawn(Up)));
+        assert_eq!(1, game.history.len());
+    }
+
+    #[test]
+    fn play_stops_at_zero_walls() {
+        let mut game = Quoridor::new(RuleSet::standard_two_player().set_walls_per_player(1));
+        game.play(Turn::PlaceWall((0, 4), Orientation::Horizontal)).unwrap();
+        game.play(Turn::PlaceWall((2, 4), Orientation::Horizontal)).unwrap();
+        assert_eq!(
+            Err(IllegalMove::NoWallsLeft),
+            game.play(Turn::PlaceWall((4, 4), Orientation::Horizontal))
+        );
+        assert_eq!(0, game.walls_left(0));
+        assert_eq!(2, game.history.len());
+    }
+
+    #[test]
+    fn play_reports_winner_and_stops() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..7 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            let side = if game.pawn(1).0 == 4 { Left } else { Right };
+            game.play(Turn::MovePawn(side)).unwrap();
+        }
+        assert_eq!(Ok(GameStatus::Won { player: 0 }), game.play(Turn::MovePawn(Up)));
+        assert_eq!(Err(IllegalMove::GameOver), game.play(Turn::MovePawn(Left)));
+        assert_eq!(15, game.history.len());
+    }
+
+    fn snapshot(game: &Quoridor) -> (Board, i32, Vec<i32>, Vec<Turn>) {
+        return (
+            game.board.clone(),
+            game.current_player,
+            game.walls_left.clone(),
+            game.history.clone(),
+        );
+    }
+
+    #[test]
+    fn resigned_games_cant_be_undone() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.resign(1).unwrap();
+        assert_eq!(None, game.undo());
+        assert_eq!(1, game.history().len());
+        assert_eq!(GameStatus::Resigned { player: 1 }, game.status());
+
+        game.ending = None;
+        game.offer_draw(1).unwrap();
+        game.offer_draw(0).unwrap();
+        assert_eq!(None, game.undo());
+        assert_eq!(GameStatus::DrawAgreed, game.status());
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn undo_restores_game_exactly() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        let turns = [
+            Turn::MovePawn(Up),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Up),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Up),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Up),
+            // Jump straight over the other pawn
+            Turn::MovePawn(Down),
+            Turn::PlaceWall((3, 2), Orientation::Horizontal),
+            Turn::PlaceWall((6, 6), Orientation::Vertical),
+            // Jump diagonally as the wall blocks the straight jump
+            Turn::JumpPawn(Down, Left),
+        ];
+        let mut snapshots = vec![snapshot(&game)];
+        for turn in turns.iter() {
+            game.play(*turn).unwrap();
+            snapshots.push(snapshot(&game));
+        }
+        assert_eq!((3, 3), game.pawn(0));
+
+        for turn in turns.iter().rev() {
+            snapshots.pop();
+            assert_eq!(Some(*turn), game.undo());
+            assert_eq!(*snapshots.last().unwrap(), snapshot(&game));
+        }
+        assert_eq!(None, game.undo());
+    }
+
+    #[test]
+    fn redo_replays_undone_turns() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((4, 4), Orientation::Horizontal)).unwrap();
+        let after = snapshot(&game);
+
+        game.undo();
+        game.undo();
+        assert_eq!(10, game.walls_left(1));
+        assert_eq!(Some(Turn::MovePawn(Up)), game.redo());
+        assert_eq!(Some(Turn::PlaceWall((4, 4), Orientation::Horizontal)), game.redo());
+        assert_eq!(None, game.redo());
+        assert_eq!(after, snapshot(&game));
+
+        // Playing a new turn throws away anything that could be redone
+        game.undo();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        assert_eq!(None, game.redo());
+    }
+
+    #[test]
+    fn resigning_ends_the_game() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert_eq!(Ok(GameStatus::Resigned { player: 0 }), game.resign(0));
+        assert!(game.is_over());
+        assert_eq!(Err(IllegalMove::GameOver), game.play(Turn::MovePawn(Down)));
+        assert_eq!(Err(IllegalMove::GameOver), game.resign(1));
+    }
+
+    #[test]
+    fn draw_needs_every_player() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        assert_eq!(Ok(GameStatus::InProgress), game.offer_draw(0));
+        assert_eq!(Ok(GameStatus::DrawAgreed), game.offer_draw(1));
+        assert_eq!(Err(IllegalMove::GameOver), game.play(Turn::MovePawn(Up)));
+
+        let mut game = Quoridor::new_four_player();
+        for player in 0..3 {
+            assert_eq!(Ok(GameStatus::InProgress), game.offer_draw(player));
+        }
+        assert_eq!(Ok(GameStatus::DrawAgreed), game.offer_draw(3));
+    }
+
+    #[test]
+    fn playing_on_declines_draw() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.offer_draw(0).unwrap();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert_eq!(&[0], game.draw_offers());
+
+        // Player 2 plays on rather than accepting
+        game.play(Turn::MovePawn(Down)).unwrap();
+        assert!(game.draw_offers().is_empty());
+        assert_eq!(Ok(GameStatus::InProgress), game.offer_draw(1));
+    }
+
+    #[test]
+    fn turn_limit_draws() {
+        use Direction::*;
+        let mut game = Quoridor::new(RuleSet::standard_two_player().set_turn_limit(Some(3)));
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        assert_eq!(Ok(GameStatus::DrawByRule), game.play(Turn::MovePawn(Up)));
+        assert_eq!(Err(IllegalMove::GameOver), game.play(Turn::MovePawn(Down)));
+        game.undo();
+        assert_eq!(GameStatus::InProgress, game.status());
+    }
+
+    /// Every turn checked one at a time with `is_valid`
+    fn brute_force_turns(game: &Quoridor) -> Vec<Turn> {
+        let mut turns: Vec<Turn> = game.pawn_turns().filter(|turn| game.is_valid(*turn)).collect();
+        for y in 0..game.board.get_height() - 1 {
+            for x in 0..game.board.get_width() - 1 {
+                for orientation in [Orientation::Horizontal, Orientation::Vertical].iter() {
+                    let turn = Turn::PlaceWall((x, y), *orientation);
+                    if game.is_valid(turn) {
+                        turns.push(turn);
+                    }
+                }
+            }
+        }
+        return turns;
+    }
+
+    #[test]
+    fn boxed_in_pawns_pass() {
+        // Player 1 on a1 is walled in above, and can't jump player 2 on b1
+        // because player 4 is behind it
+        let mut game = Quoridor::from_position_string("9x9 a1,b1,e9,c1 a1h 0,5,5,5 1").unwrap();
+        assert_eq!(1, game.legal_turn_count());
+        assert_eq!(Some("pass"), Turn::Pass.to_notation(&game).as_deref());
+        assert_eq!(Ok(Turn::Pass), Turn::from_notation("pass", &game));
+        game.play(Turn::Pass).unwrap();
+        assert_eq!(1, game.current_player());
+        assert_eq!(Err(IllegalMove::CantPass), game.play(Turn::Pass));
+        assert_eq!(Some(Turn::Pass), game.undo());
+        assert_eq!(0, game.current_player());
+        assert_eq!(Some(Turn::Pass), game.redo());
+        assert!(game.history_to_str().ends_with("Pass\n"));
+
+        assert_eq!(Err(IllegalMove::CantPass), Quoridor::new_two_player().validate(Turn::Pass));
+    }
+
+    #[test]
+    fn legal_turns_at_start() {
+        use Direction::*;
+        let game = Quoridor::new_two_player();
+        let turns: Vec<Turn> = game.legal_turns().collect();
+        assert_eq!(3 + 128, turns.len());
+        assert_eq!(131, game.legal_turn_count());
+        assert_eq!(&[Turn::MovePawn(Up), Turn::MovePawn(Right), Turn::MovePawn(Left)], &turns[..3]);
+    }
+
+    #[test]
+    fn legal_turns_match_brute_force() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        let turns = [
+            Turn::PlaceWall((0, 2), Orientation::Horizontal),
+            Turn::PlaceWall((2, 2), Orientation::Horizontal),
+            Turn::PlaceWall((4, 2), Orientation::Horizontal),
+            Turn::PlaceWall((6, 2), Orientation::Horizontal),
+            Turn::PlaceWall((7, 2), Orientation::Vertical),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Right),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Right),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Right),
+            Turn::MovePawn(Down),
+            Turn::MovePawn(Right),
+            Turn::MovePawn(Down),
+        ];
+        for turn in turns.iter() {
+            let mut expected = brute_force_turns(&game);
+            let mut found: Vec<Turn> = game.legal_turns().collect();
+            expected.sort_by_key(|turn| format!("{:?}", turn));
+            found.sort_by_key(|turn| format!("{:?}", turn));
+            assert_eq!(expected, found);
+            assert_eq!(expected.len(), game.legal_turn_count());
+            game.play(*turn).unwrap();
+        }
+
+        // The wall that would seal player 1 in is left out
+        assert_eq!((8, 0), game.pawn(0));
+        assert!(!game
+            .legal_turns()
+            .any(|turn| turn == Turn::PlaceWall((7, 1), Orientation::Horizontal)));
+    }
+
+    #[test]
+    fn legal_turns_include_jumps() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..3 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            game.play(Turn::MovePawn(Down)).unwrap();
+        }
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((4, 3), Orientation::Horizontal)).unwrap();
+        let pawn_turns: Vec<Turn> = game
+            .legal_turns()
+            .filter(|turn| !matches!(turn, Turn::PlaceWall(..)))
+            .collect();
+        assert_eq!(
+            vec![Turn::MovePawn(Up), Turn::MovePawn(Right), Turn::MovePawn(Left)],
+            pawn_turns
+        );
+
+        game.play(Turn::PlaceWall((0, 6), Orientation::Horizontal)).unwrap();
+        let pawn_turns: Vec<Turn> = game
+            .legal_turns()
+            .filter(|turn| !matches!(turn, Turn::PlaceWall(..)))
+            .collect();
+        assert_eq!(
+            vec![
+                Turn::MovePawn(Up),
+                Turn::MovePawn(Right),
+                Turn::MovePawn(Left),
+                Turn::JumpPawn(Down, Right),
+                Turn::JumpPawn(Down, Left),
+            ],
+            pawn_turns
+        );
+    }
+
+    #[test]
+    fn no_legal_turns_when_over() {
+        let mut game = Quoridor::new_two_player();
+        game.resign(0).unwrap();
+        assert_eq!(0, game.legal_turns().count());
+        assert_eq!(0, game.legal_turn_count());
+    }
+
+    #[test]
+    fn history_in_notation() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..3 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            game.play(Turn::MovePawn(Down)).unwrap();
+        }
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        game.play(Turn::PlaceWall((0, 0), Orientation::Vertical)).unwrap();
+        assert_eq!(
+            vec!["e2", "e8", "e3", "e7", "e4", "e6", "e5", "e4", "a1v"],
+            game.history_to_notation()
+        );
+        // Writing the history out leaves the game alone
+        assert_eq!(9, game.history.len());
+        assert_eq!((4, 3), game.pawn(1));
+
+        game.resign(1).unwrap();
+        assert_eq!(9, game.history_to_notation().len());
+    }
+
+    #[test]
+    fn rewound_game_replays() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((0, 0), Orientation::Vertical)).unwrap();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.undo();
+        game.resign(0).unwrap();
+
+        let mut replay = game.rewound();
+        assert_eq!(snapshot(&Quoridor::new_two_player()), snapshot(&replay));
+        assert_eq!(GameStatus::InProgress, replay.status());
+        assert_eq!(Some(Turn::MovePawn(Up)), replay.redo());
+        assert_eq!(Some(Turn::PlaceWall((0, 0), Orientation::Vertical)), replay.redo());
+        assert_eq!(None, replay.redo());
+        assert_eq!(game.to_string(), replay.to_string());
+    }
+
+    #[test]
+    fn position_string_round_trip() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        assert_eq!("9x9 e1,e9 - 10,10 1", game.to_position_string());
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((3, 4), Orientation::Vertical)).unwrap();
+        game.play(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        let position = game.to_position_string();
+        assert_eq!("9x9 e2,e9 e3h,d5v 9,9 2", position);
+
+        let loaded = Quoridor::from_position_string(&position).unwrap();
+        assert_eq!(position, loaded.to_position_string());
+        assert_eq!(game.to_string(), loaded.to_string());
+        assert_eq!(game.legal_turn_count(), loaded.legal_turn_count());
+
+        let mut game = Quoridor::new_four_player();
+        for _ in 0..6 {
+            let turn = game.legal_turns().last().unwrap();
+            game.play(turn).unwrap();
+        }
+        let position = game.to_position_string();
+        let loaded = Quoridor::from_position_string(&position).unwrap();
+        assert_eq!(position, loaded.to_position_string());
+        assert_eq!(game.to_string(), loaded.to_string());
+    }
+
+    #[test]
+    fn position_string_keeps_rules() {
+        use Direction::*;
+        let rules = RuleSet::standard_two_player()
+            .set_width(11)
+            .set_height(11)
+            .set_walls_per_player(15);
+        let mut game = Quoridor::new(rules.clone());
+        assert_eq!("11x11 f1,f11 - 15,15 1 walls=15", game.to_position_string());
+        game.play(Turn::PlaceWall((9, 9), Orientation::Horizontal)).unwrap();
+        let loaded = Quoridor::from_position_string(&game.to_position_string()).unwrap();
+        assert_eq!(&rules, loaded.rules());
+        assert_eq!(14, loaded.walls_left(0));
+        assert_eq!(game.to_string(), loaded.to_string());
+
+        // Starting on the sides means racing across the board
+        let rules = RuleSet::standard_two_player()
+            .set_players(3)
+            .set_starting_squares(vec![(0, 3), (8, 4), (4, 8)]);
+        let mut game = Quoridor::new(rules.clone());
+        game.play(Turn::MovePawn(Right)).unwrap();
+        let position = game.to_position_string();
+        assert_eq!("9x9 b4,i5,e9 - 10,10,10 2 start=a4,i5,e9", position);
+        let loaded = Quoridor::from_position_string(&position).unwrap();
+        assert_eq!(&rules, loaded.rules());
+        assert_eq!(game.legal_turn_count(), loaded.legal_turn_count());
+
+        assert!(Quoridor::from_position_string("9x9 e1,e9 - 10,10 1 walls=x").is_err());
+        assert!(Quoridor::from_position_string("9x9 e1,e9 - 10,10 1 start=e1").is_err());
+        assert!(Quoridor::from_position_string("9x9 e1,e9 - 10,10 1 colour=red").is_err());
+    }
+
+    #[test]
+    fn position_string_keeps_goals() {
+        let game = Quoridor::from_position_string("5x5 c4,c2 - 10,10 1").unwrap();
+        assert_eq!(5, game.rules().get_width());
+        let game = Quoridor::from_position_string("5x5 c5,c2 - 10,10 2").unwrap();
+        assert_eq!(GameStatus::Won { player: 0 }, game.status());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn turns_as_json() {
+        use Direction::*;
+        let wall = Turn::PlaceWall((4, 2), Orientation::Horizontal);
+        let turns = [
+            (wall, r#"{"PlaceWall":[[4,2],"Horizontal"]}"#),
+            (Turn::MovePawn(Up), r#"{"MovePawn":"Up"}"#),
+            (Turn::JumpPawn(Up, Left), r#"{"JumpPawn":["Up","Left"]}"#),
+        ];
+        for (turn, json) in turns.iter() {
+            assert_eq!(*json, serde_json::to_string(turn).unwrap());
+            assert_eq!(*turn, serde_json::from_str::<Turn>(json).unwrap());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn game_json_round_trip() {
+        use Direction::*;
+        let mut game = Quoridor::new_four_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((3, 4), Orientation::Vertical)).unwrap();
+        game.play(Turn::MovePawn(Left)).unwrap();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        game.undo();
+        game.offer_draw(2).unwrap();
+
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: Quoridor = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot(&game), snapshot(&loaded));
+        assert_eq!(game.draw_offers(), loaded.draw_offers());
+        assert_eq!(json, serde_json::to_string(&loaded).unwrap());
+
+        game.resign(1).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: Quoridor = serde_json::from_str(&json).unwrap();
+        assert_eq!(GameStatus::Resigned { player: 1 }, loaded.status());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_games_are_checked() {
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Direction::Up)).unwrap();
+        let json = serde_json::to_value(&game).unwrap();
+        assert!(serde_json::from_value::<Quoridor>(json.clone()).is_ok());
+
+        let tampered = [
+            ("/current_player", serde_json::json!(5)),
+            ("/walls_left/0", serde_json::json!(11)),
+            ("/walls_left", serde_json::json!([10])),
+            ("/board/pawns/0", serde_json::json!([4, 0])),
+            ("/board/pawns/1", serde_json::json!([4, 1])),
+            ("/board/width", serde_json::json!(7)),
+            ("/board/goals/0", serde_json::json!({"Edge": "Left"})),
+            ("/board/walls", serde_json::json!([{"Wall": [[8, 0], "Horizontal"]}])),
+            ("/history/0", serde_json::json!({"JumpPawn": ["Up", "Left"]})),
+            ("/undone", serde_json::json!([{"MovePawn": "Up"}, {"MovePawn": "Up"}])),
+            ("/draw_offers", serde_json::json!([3])),
+            ("/ending", serde_json::json!({"Won": {"player": 0}})),
+            ("/rules/players", serde_json::json!(3)),
+        ];
+        for (pointer, value) in tampered.iter() {
+            let mut json = json.clone();
+            *json.pointer_mut(pointer).unwrap() = value.clone();
+            assert!(
+                serde_json::from_value::<Quoridor>(json).is_err(),
+                "{} = {} was accepted",
+                pointer,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_positions_are_refused() {
+        let refused = [
+            "9x9 e1,e9 - 10,10",
+            "9by9 e1,e9 - 10,10 1",
+            "1x9 a1,a9 - 10,10 1",
+            "9x9 e1,e0 - 10,10 1",
+            "9x9 e1,j9 - 10,10 1",
+            "9x9 e1,e1 - 10,10 1",
+            "9x9 e1,e9,e5 - 10,10 1",
+            "9x9 e1,e9 - 10,10 3",
+            "9x9 e1,e9 - 11,10 1",
+            "9x9 e1,e9 - 10,-1 1",
+            "9x9 e1,e9 e3h 10,10 1",
+            "9x9 e1,e9 e3x 9,10 1",
+            "9x9 e1,e9 e3h,e3v 9,9 1",
+            "9x9 e1,e9 e3h,f3h 9,9 1",
+            "9x9 e1,e9 i3h 9,10 1",
+            "9x9 e1,e9 a1h,c1h,e1h,g1h,h1v,h2h 4,10 1",
+        ];
+        for position in refused.iter() {
+            assert!(
+                Quoridor::from_position_string(position).is_err(),
+                "{} was accepted",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn position_hash_follows_the_position() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        let start = game.position_hash();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        let after_one = game.position_hash();
+        assert_ne!(start, after_one);
+        game.play(Turn::MovePawn(Down)).unwrap();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        // Back where we started by a different route
+        assert_eq!(start, game.position_hash());
+        game.undo();
+        game.undo();
+        game.undo();
+        assert_eq!(after_one, game.position_hash());
+
+        // The same walls in a different order
+        let mut first = Quoridor::new_two_player();
+        first.play(Turn::PlaceWall((0, 3), Orientation::Horizontal)).unwrap();
+        first.play(Turn::PlaceWall((5, 5), Orientation::Vertical)).unwrap();
+        let mut second = Quoridor::new_two_player();
+        second.play(Turn::PlaceWall((5, 5), Orientation::Vertical)).unwrap();
+        second.play(Turn::PlaceWall((0, 3), Orientation::Horizontal)).unwrap();
+        assert_eq!(first.position_hash(), second.position_hash());
+        second.undo();
+        assert_ne!(first.position_hash(), second.position_hash());
+    }
+
+    #[test]
+    fn position_hash_is_kept_up_to_date() {
+        let mut game = Quoridor::new_two_player();
+        for i in 0..30 {
+            let turns: Vec<Turn> = game.legal_turns().collect();
+            game.play(turns[i * 7 % turns.len()]).unwrap();
+            assert_eq!(game.full_hash(), game.position_hash());
+        }
+        while game.undo().is_some() {
+            assert_eq!(game.full_hash(), game.position_hash());
+        }
+        while game.redo().is_some() {
+            assert_eq!(game.full_hash(), game.position_hash());
+        }
+
+        let mut boxed_in = Quoridor::from_position_string("9x9 a1,b1,e9,c1 a1h 0,5,5,5 1").unwrap();
+        assert_eq!(boxed_in.full_hash(), boxed_in.position_hash());
+        boxed_in.play(Turn::Pass).unwrap();
+        assert_eq!(boxed_in.full_hash(), boxed_in.position_hash());
+        boxed_in.undo();
+        assert_eq!(boxed_in.full_hash(), boxed_in.position_hash());
+    }
+
+    #[test]
+    fn position_hash_covers_walls_left_and_side() {
+        let game = Quoridor::from_position_string("9x9 e2,e8 - 10,10 1").unwrap();
+        let other_side = Quoridor::from_position_string("9x9 e2,e8 - 10,10 2").unwrap();
+        let fewer_walls = Quoridor::from_position_string("9x9 e2,e8 - 9,10 1").unwrap();
+        assert_eq!(game.board().get_hash(), other_side.board().get_hash());
+        assert_ne!(game.position_hash(), other_side.position_hash());
+        assert_ne!(game.position_hash(), fewer_walls.position_hash());
+        let loaded = Quoridor::from_bytes(&game.to_bytes().unwrap()).unwrap();
+        assert_eq!(game.position_hash(), loaded.position_hash());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        use std::convert::TryInto;
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((3, 4), Orientation::Vertical)).unwrap();
+        game.play(Turn::PlaceWall((7, 7), Orientation::Horizontal)).unwrap();
+        let bytes = game.to_bytes().unwrap();
+        assert_eq!([9, 9, 2, 13, 76, 0xFF, 0xFF, 9, 9, 0xFF, 0xFF, 1], bytes[..12]);
+        assert_eq!(1 << 63, u64::from_le_bytes(bytes[12..20].try_into().unwrap()));
+        assert_eq!(1 << 35, u64::from_le_bytes(bytes[20..28].try_into().unwrap()));
+
+        let loaded = Quoridor::from_bytes(&bytes).unwrap();
+        assert_eq!(bytes, loaded.to_bytes().unwrap());
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+
+        let mut game = Quoridor::new_four_player();
+        for _ in 0..9 {
+            let turn = game.legal_turns().last().unwrap();
+            game.play(turn).unwrap();
+        }
+        let loaded = Quoridor::from_bytes(&game.to_bytes().unwrap()).unwrap();
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+    }
+
+    #[test]
+    fn bytes_only_fit_standard_rules() {
+        let rules = RuleSet::standard_two_player().set_walls_per_player(12);
+        assert!(Quoridor::new(rules).to_bytes().is_err());
+        let rules = RuleSet::standard_two_player().set_starting_squares(vec![(0, 4), (8, 4)]);
+        assert!(Quoridor::new(rules).to_bytes().is_err());
+        let rules = RuleSet::standard_two_player().set_players(3);
+        let game = Quoridor::new(rules.clone());
+        let loaded = Quoridor::from_bytes(&game.to_bytes().unwrap()).unwrap();
+        assert_eq!(&rules, loaded.rules());
+    }
+
+    #[test]
+    fn bytes_only_fit_small_boards() {
+        let rules = RuleSet::standard_two_player().set_width(11).set_height(11);
+        assert!(Quoridor::new(rules).to_bytes().is_err());
+        let rules = RuleSet::standard_two_player().set_width(5).set_height(7);
+        let game = Quoridor::new(rules);
+        let loaded = Quoridor::from_bytes(&game.to_bytes().unwrap()).unwrap();
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+    }
+
+    #[test]
+    fn impossible_bytes_are_refused() {
+        let bytes = Quoridor::new_two_player().to_bytes().unwrap();
+        let changes = [
+            (0, 10),
+            (2, 3),
+            (2, 0),
+            (4, 4),
+            (4, 81),
+            (7, 11),
+            (11, 2),
+            // A wall without a player having used one
+            (12, 1),
+            // Unused pawn and wall slots, so every position has one encoding
+            (5, 0),
+            (6, 40),
+            (9, 0),
+            (10, 10),
+        ];
+        for (index, value) in changes.iter() {
+            let mut bytes = bytes;
+            bytes[*index] = *value;
+            assert!(
+                Quoridor::from_bytes(&bytes).is_err(),
+                "byte {} = {} was accepted",
+                index,
+                value
+            );
+        }
+
+        // Walls crossing at the corner of a1
+        let mut bytes = bytes;
+        bytes[7] = 8;
+        bytes[12] = 1;
+        bytes[20] = 1;
+        assert!(Quoridor::from_bytes(&bytes).is_err());
+    }
+
     #[test]
     fn test_can_move_pawn() {
         let mut game = Quoridor::new_two_player();
@@ -251,4 +1955,22 @@ mod tests {
         assert!(game.has_won());
     }
 
+    #[test]
+    fn can_jump_pawn() {
+        use Direction::*;
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..3 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            game.play(Turn::MovePawn(Down)).unwrap();
+        }
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert_eq!((4, 4), game.pawn(0));
+        assert_eq!((4, 5), game.pawn(1));
+
+        assert!(game.is_valid(Turn::MovePawn(Down)));
+        assert!(!game.is_valid(Turn::JumpPawn(Down, Left)));
+        game.play(Turn::MovePawn(Down)).unwrap();
+        assert_eq!((4, 3), game.pawn(1));
+    }
+
 }
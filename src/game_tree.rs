@@ -0,0 +1,520 @@
+#![allow(dead_code)]
+
+//! A game with variations, for looking at what else could have been played
+//! without losing the main line.
+//!
+//! The tree starts at the position before the first turn and each node is a
+//! turn played from its parent. The first child of a node carries on the
+//! main line, any others are variations.
+
+use crate::game::{Quoridor, Turn};
+use crate::illegal_move::IllegalMove;
+use crate::record::{result_to_str, GameRecord, RecordMove};
+use crate::rules::RuleSet;
+
+/// Refers to a node in a `GameTree`. Ids stay the same as the tree changes,
+/// but the id of a deleted node is never used again.
+pub type NodeId = usize;
+
+#[derive(Clone, Debug)]
+struct Node {
+    /// `None` for the root
+    turn: Option<Turn>,
+    parent: Option<NodeId>,
+    /// The main line first, then the variations
+    children: Vec<NodeId>,
+    comment: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameTree {
+    /// The tags of the record the tree was made from, without moves
+    header: GameRecord,
+    /// `None` where a node has been deleted
+    nodes: Vec<Option<Node>>,
+    current: NodeId,
+    /// The position at the root
+    start: Quoridor,
+    /// The position at the current node
+    game: Quoridor,
+}
+
+impl GameTree {
+    /// A tree holding just the start of a game.
+    pub fn new(rules: RuleSet) -> GameTree {
+        let start = Quoridor::new(rules);
+        return GameTree {
+            header: GameRecord::from_game(&start),
+            nodes: vec![Some(Node {
+                turn: None,
+                parent: None,
+                children: Vec::new(),
+                comment: None,
+            })],
+            current: 0,
+            game: start.clone(),
+            start,
+        };
+    }
+
+    /// A tree with the game's history as its main line, at the last turn.
+    pub fn from_game(game: &Quoridor) -> GameTree {
+        let mut tree = GameTree::new(game.rules().clone());
+        tree.start = game.rewound();
+        tree.game = tree.start.clone();
+        tree.header = GameRecord::from_game(game)
+            .remove_tag("Undone")
+            .remove_tag("DrawOffers")
+            .set_moves(Vec::new());
+        for turn in game.history() {
+            tree.add_turn(*turn).unwrap();
+        }
+        tree.go_to(tree.current);
+        return tree;
+    }
+
+    /// A tree with the record's main line and variations, at the start.
+    pub fn from_record(record: &GameRecord) -> Result<GameTree, String> {
+        let mut tree = GameTree::new(record.rules()?);
+        tree.start = record.start()?;
+        tree.game = tree.start.clone();
+        tree.set_comment(tree.root(), record.get_comment().map(String::from));
+        tree.add_record_moves(tree.root(), record.get_moves())?;
+        // The header can only end the game once the main line is all there
+        let header = record
+            .clone()
+            .remove_tag("Undone")
+            .remove_tag("DrawOffers")
+            .set_comment(None)
+            .set_moves(Vec::new());
+        header.end_game(&mut tree.game_at(*tree.main_line().last().unwrap_or(&tree.root())))?;
+        tree.header = header;
+        tree.go_to(tree.root());
+        return Ok(tree);
+    }
+
+    fn add_record_moves(&mut self, parent: NodeId, moves: &[RecordMove]) -> Result<(), String> {
+        let mut parent = parent;
+        for (i, record_move) in moves.iter().enumerate() {
+            self.go_to(parent);
+            let node = self
+                .add_turn(record_move.turn)
+                .map_err(|reason| format!("turn {} is illegal: {}", i + 1, reason))?;
+            if record_move.comment.is_some() {
+                self.set_comment(node, record_move.comment.clone());
+            }
+            for variation in record_move.variations.iter() {
+                self.add_record_moves(parent, variation)?;
+            }
+            parent = node;
+        }
+        return Ok(());
+    }
+
+    /// The record of the tree, variations and comments included. The result
+    /// comes from the end of the main line, unless the game was resigned or
+    /// drawn by agreement.
+    pub fn to_record(&self) -> GameRecord {
+        let root = self.node(self.root());
+        let moves = match root.children.first() {
+            Some(first) => self.record_line(*first, true),
+            None => Vec::new(),
+        };
+        let mut record = self
+            .header
+            .clone()
+            .set_comment(root.comment.clone())
+            .set_moves(moves);
+
+        let termination = record.get_tag("Termination");
+        if !matches!(termination, Some("Resignation") | Some("Agreement")) {
+            let end = self.game_at(*self.main_line().last().unwrap_or(&self.root()));
+            let ended = GameRecord::from_game(&end);
+            record = record.set_tag("Result", &result_to_str(&end));
+            record = match ended.get_tag("Termination") {
+                Some(termination) => record.set_tag("Termination", termination),
+                None => record.remove_tag("Termination"),
+            };
+        }
+        return record;
+    }
+
+    /// The line starting at `first` and carrying on down the main line.
+    /// Variations are only written for turns that have siblings to list.
+    fn record_line(&self, first: NodeId, with_siblings: bool) -> Vec<RecordMove> {
+        let mut moves = Vec::new();
+        let mut node = Some(first);
+        let mut with_siblings = with_siblings;
+        while let Some(id) = node {
+            let current = self.node(id);
+            let mut record_move = RecordMove::new(current.turn.unwrap());
+            record_move.comment = current.comment.clone();
+            if with_siblings {
+                let siblings = &self.node(current.parent.unwrap()).children;
+                record_move.variations = siblings[1..]
+                    .iter()
+                    .map(|sibling| self.record_line(*sibling, false))
+                    .collect();
+            }
+            moves.push(record_move);
+            node = current.children.first().copied();
+            with_siblings = true;
+        }
+        return moves;
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        return self.nodes[id].as_ref().expect("Node has been deleted");
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        return self.nodes[id].as_mut().expect("Node has been deleted");
+    }
+
+    pub fn root(&self) -> NodeId {
+        return 0;
+    }
+
+    pub fn current(&self) -> NodeId {
+        return self.current;
+    }
+
+    /// The position at the current node.
+    pub fn game(&self) -> &Quoridor {
+        return &self.game;
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        return matches!(self.nodes.get(id), Some(Some(_)));
+    }
+
+    /// The turn that leads to a node, `None` for the root.
+    pub fn turn(&self, id: NodeId) -> Option<Turn> {
+        return self.node(id).turn;
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        return self.node(id).parent;
+    }
+
+    /// The turns played from a node, the main line first.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        return &self.node(id).children;
+    }
+
+    pub fn comment(&self, id: NodeId) -> Option<&str> {
+        return self.node(id).comment.as_deref();
+    }
+
+    pub fn set_comment(&mut self, id: NodeId, comment: Option<String>) {
+        self.node_mut(id).comment = comment;
+    }
+
+    /// The nodes from the root down to `id`, not counting the root.
+    pub fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.node(node).parent {
+            path.push(node);
+            node = parent;
+        }
+        path.reverse();
+        return path;
+    }
+
+    /// The nodes along the main line from the root, not counting the root.
+    pub fn main_line(&self) -> Vec<NodeId> {
+        let mut line = Vec::new();
+        let mut node = self.root();
+        while let Some(child) = self.node(node).children.first() {
+            line.push(*child);
+            node = *child;
+        }
+        return line;
+    }
+
+    /// Plays out the turns from the root to a node. The game at the end of
+    /// the main line ends the way the header says, if it was resigned or
+    /// drawn by agreement.
+    fn game_at(&self, id: NodeId) -> Quoridor {
+        let mut game = self.start.clone();
+        for node in self.path_to(id) {
+            game.play(self.node(node).turn.unwrap())
+                .expect("Tree holds an illegal turn");
+        }
+        if self.ends_main_line(id) {
+            self.header.end_game(&mut game).ok();
+        }
+        return game;
+    }
+
+    fn ends_main_line(&self, id: NodeId) -> bool {
+        return self.main_line().last().unwrap_or(&self.root()) == &id;
+    }
+
+    /// Moves to a node, panics if it has been deleted.
+    pub fn go_to(&mut self, id: NodeId) {
+        self.game = self.game_at(id);
+        self.current = id;
+    }
+
+    /// Moves to the parent of the current node, false at the root.
+    pub fn back(&mut self) -> bool {
+        return match self.node(self.current).parent {
+            Some(parent) => {
+                // A game that was resigned or agreed drawn can't be undone
+                if self.game.undo().is_none() {
+                    self.game = self.game_at(parent);
+                }
+                self.current = parent;
+                true
+            }
+            None => false,
+        };
+    }
+
+    /// Moves along the main line from the current node, false at the end.
+    pub fn forward(&mut self) -> bool {
+        return match self.node(self.current).children.first() {
+            Some(child) => {
+                let child = *child;
+                self.game.play(self.node(child).turn.unwrap()).unwrap();
+                if self.ends_main_line(child) {
+                    self.header.end_game(&mut self.game).ok();
+                }
+                self.current = child;
+                true
+            }
+            None => false,
+        };
+    }
+
+    /// Plays a turn from the current node and moves to it.
+    ///
+    /// If the turn has been played from here before the tree stays the same,
+    /// otherwise it is added, as the main line if there are no other turns
+    /// from here or as a new variation if there are.
+    pub fn add_turn(&mut self, turn: Turn) -> Result<NodeId, IllegalMove> {
+        let existing = self
+            .node(self.current)
+            .children
+            .iter()
+            .find(|child| self.node(**child).turn == Some(turn))
+            .copied();
+        if let Some(child) = existing {
+            self.game.play(turn)?;
+            self.current = child;
+            return Ok(child);
+        }
+
+        self.game.play(turn)?;
+        let id = self.nodes.len();
+        self.nodes.push(Some(Node {
+            turn: Some(turn),
+            parent: Some(self.current),
+            children: Vec::new(),
+            comment: None,
+        }));
+        self.node_mut(self.current).children.push(id);
+        self.current = id;
+        return Ok(id);
+    }
+
+    /// Makes the line leading to a node the main line, by moving each
+    /// node on the way to the front of its parent's children.
+    pub fn promote(&mut self, id: NodeId) {
+        for node in self.path_to(id) {
+            let parent = self.node(node).parent.unwrap();
+            let children = &mut self.node_mut(parent).children;
+            children.retain(|child| *child != node);
+            children.insert(0, node);
+        }
+    }
+
+    /// Deletes a node along with every turn after it. If the current node
+    /// goes with it the tree moves back to the node's parent. The root
+    /// can't be deleted, false is returned if asked to.
+    pub fn delete(&mut self, id: NodeId) -> bool {
+        let parent = match self.node(id).parent {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let current_deleted = self.path_to(self.current).contains(&id);
+        self.node_mut(parent).children.retain(|child| *child != id);
+        let mut to_delete = vec![id];
+        while let Some(node) = to_delete.pop() {
+            if let Some(node) = self.nodes[node].take() {
+                to_delete.extend(node.children);
+            }
+        }
+        if current_deleted {
+            self.go_to(parent);
+        }
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::GameStatus;
+    use crate::board::Direction::*;
+    use crate::board::Orientation;
+    use crate::record::read_records;
+
+    fn up_and_down() -> Quoridor {
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..2 {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            game.play(Turn::MovePawn(Down)).unwrap();
+        }
+        return game;
+    }
+
+    #[test]
+    fn main_line_from_game() {
+        let game = up_and_down();
+        let mut tree = GameTree::from_game(&game);
+        assert_eq!(4, tree.main_line().len());
+        assert_eq!(*tree.main_line().last().unwrap(), tree.current());
+        assert_eq!(game.to_string(), tree.game().to_string());
+
+        assert!(tree.back());
+        assert!(tree.back());
+        assert_eq!(2, tree.game().history().len());
+        assert!(tree.forward());
+        assert_eq!(Some(Turn::MovePawn(Up)), tree.game().history().last().copied());
+        tree.go_to(tree.root());
+        assert!(!tree.back());
+        assert!(tree.game().history().is_empty());
+    }
+
+    #[test]
+    fn variations_keep_the_main_line() {
+        let mut tree = GameTree::from_game(&up_and_down());
+        let main_line = tree.main_line();
+        tree.go_to(main_line[1]);
+        let wall = tree.add_turn(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        let after_wall = tree.add_turn(Turn::MovePawn(Left)).unwrap();
+        assert_eq!(main_line, tree.main_line());
+        assert_eq!(&[main_line[2], wall], tree.children(main_line[1]));
+        assert_eq!(vec![main_line[0], main_line[1], wall, after_wall], tree.path_to(after_wall));
+
+        // Playing a turn that is already there just moves to it
+        tree.go_to(main_line[1]);
+        assert_eq!(Ok(main_line[2]), tree.add_turn(Turn::MovePawn(Up)));
+        assert_eq!(2, tree.children(main_line[1]).len());
+
+        // Illegal turns are refused
+        tree.go_to(tree.root());
+        assert_eq!(Err(IllegalMove::OffBoard), tree.add_turn(Turn::MovePawn(Down)));
+        assert_eq!(tree.root(), tree.current());
+    }
+
+    #[test]
+    fn promote_and_delete() {
+        let mut tree = GameTree::from_game(&up_and_down());
+        let main_line = tree.main_line();
+        tree.go_to(main_line[1]);
+        let wall = tree.add_turn(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        let after_wall = tree.add_turn(Turn::MovePawn(Left)).unwrap();
+
+        tree.promote(after_wall);
+        assert_eq!(vec![main_line[0], main_line[1], wall, after_wall], tree.main_line());
+        assert_eq!(&[wall, main_line[2]], tree.children(main_line[1]));
+
+        assert!(tree.delete(wall));
+        assert!(!tree.contains(wall));
+        assert!(!tree.contains(after_wall));
+        assert_eq!(main_line[1], tree.current());
+        assert_eq!(main_line, tree.main_line());
+        assert!(!tree.delete(tree.root()));
+    }
+
+    #[test]
+    fn tree_round_trips_through_records() {
+        let mut tree = GameTree::from_game(&up_and_down());
+        let main_line = tree.main_line();
+        tree.go_to(main_line[0]);
+        let wall = tree.add_turn(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        tree.add_turn(Turn::MovePawn(Left)).unwrap();
+        tree.go_to(main_line[0]);
+        tree.add_turn(Turn::MovePawn(Left)).unwrap();
+        tree.go_to(tree.root());
+        tree.add_turn(Turn::MovePawn(Right)).unwrap();
+        tree.set_comment(wall, Some(String::from("Blocking")));
+        tree.set_comment(tree.root(), Some(String::from("Analysis")));
+
+        let record = tree.to_record();
+        let text = record.to_string();
+        let words: Vec<&str> = text.split_whitespace().collect();
+        assert!(words.join(" ").contains(
+            "{Analysis} 1. e2 (1. f1) 1... e8 (1... e3h {Blocking} 2. d2) (1... d9) 2. e3 e7 *"
+        ));
+
+        let loaded = GameTree::from_record(&read_records(&text).unwrap()[0]).unwrap();
+        assert_eq!(record, loaded.to_record());
+        assert_eq!(loaded.root(), loaded.current());
+        assert_eq!(4, loaded.main_line().len());
+    }
+
+    #[test]
+    fn resignation_ends_the_main_line() {
+        let mut game = up_and_down();
+        game.resign(1).unwrap();
+        let record = GameTree::from_game(&game).to_record();
+        assert_eq!(Some("Resignation"), record.get_tag("Termination"));
+
+        let mut loaded = GameTree::from_record(&record).unwrap();
+        assert_eq!(GameStatus::InProgress, loaded.game().status());
+        let main_line = loaded.main_line();
+        loaded.go_to(main_line[main_line.len() - 1]);
+        assert_eq!(GameStatus::Resigned { player: 1 }, loaded.game().status());
+        assert!(loaded.back());
+        assert_eq!(GameStatus::InProgress, loaded.game().status());
+        assert!(loaded.forward());
+        assert_eq!(GameStatus::Resigned { player: 1 }, loaded.game().status());
+        assert_eq!(record, loaded.to_record());
+    }
+
+    #[test]
+    fn tree_keeps_the_starting_position() {
+        let start = "9x9 e5,e6 e3h 9,10 2";
+        let mut game = Quoridor::from_position_string(start).unwrap();
+        game.play(Turn::PlaceWall((3, 5), Orientation::Horizontal)).unwrap();
+        game.play(Turn::MovePawn(Left)).unwrap();
+        let mut tree = GameTree::from_game(&game);
+        tree.go_to(tree.root());
+        tree.add_turn(Turn::MovePawn(Right)).unwrap();
+
+        let text = tree.to_record().to_string();
+        assert!(text.contains(&format!("[Position \"{}\"]", start)), "{}", text);
+        let mut loaded = GameTree::from_record(&read_records(&text).unwrap()[0]).unwrap();
+        assert_eq!(start, loaded.game().to_position_string());
+        assert_eq!(tree.to_record(), loaded.to_record());
+        loaded.go_to(*loaded.main_line().last().unwrap());
+        assert_eq!(game.to_position_string(), loaded.game().to_position_string());
+    }
+
+    #[test]
+    fn result_follows_the_main_line() {
+        // Player 2 steps aside while player 1 walks straight up
+        let mut game = Quoridor::new_two_player();
+        for side in [Left, Right, Left, Right, Left, Right, Left].iter() {
+            game.play(Turn::MovePawn(Up)).unwrap();
+            game.play(Turn::MovePawn(*side)).unwrap();
+        }
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert!(game.is_over());
+
+        let mut tree = GameTree::from_game(&game);
+        assert_eq!(Some("1-0"), tree.to_record().get_tag("Result"));
+        let main_line = tree.main_line();
+        tree.go_to(main_line[main_line.len() - 2]);
+        let other = tree.add_turn(Turn::MovePawn(Left)).unwrap();
+        tree.promote(other);
+        let record = tree.to_record();
+        assert_eq!(Some("*"), record.get_tag("Result"));
+        assert_eq!(None, record.get_tag("Termination"));
+    }
+}
@@ -0,0 +1,116 @@
+#![allow(dead_code)]
+
+use crate::board::Point;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Edge {
+    /// The edge on the other side of the board.
+    pub fn opposite(self) -> Edge {
+        return match self {
+            Edge::Top => Edge::Bottom,
+            Edge::Right => Edge::Left,
+            Edge::Bottom => Edge::Top,
+            Edge::Left => Edge::Right,
+        };
+    }
+}
+
+/// Where a pawn has to get to in order to win.
+///
+/// Serialized as `{"Edge": "Top"}` or `{"Squares": [[0, 8], [1, 8]]}`.
+/// Predicates can't be serialized.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Goal {
+    /// Any square along one edge of the board.
+    Edge(Edge),
+    /// Any one of a set of squares.
+    Squares(Vec<Point>),
+    /// Any square the function accepts.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Predicate(Arc<dyn Fn(Point) -> bool + Send + Sync>),
+}
+
+impl Goal {
+    /// True if `point` satisfies the goal on a board of the given size.
+    pub fn contains(&self, (x, y): Point, width: i32, height: i32) -> bool {
+        return match self {
+            Goal::Edge(Edge::Top) => y == height - 1,
+            Goal::Edge(Edge::Right) => x == width - 1,
+            Goal::Edge(Edge::Bottom) => y == 0,
+            Goal::Edge(Edge::Left) => x == 0,
+            Goal::Squares(squares) => squares.contains(&(x, y)),
+            Goal::Predicate(predicate) => predicate((x, y)),
+        };
+    }
+}
+
+impl fmt::Debug for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Goal::Edge(edge) => write!(f, "Edge({:?})", edge),
+            Goal::Squares(squares) => write!(f, "Squares({:?})", squares),
+            Goal::Predicate(_) => write!(f, "Predicate(..)"),
+        };
+    }
+}
+
+impl PartialEq for Goal {
+    fn eq(&self, other: &Goal) -> bool {
+        return match (self, other) {
+            (Goal::Edge(a), Goal::Edge(b)) => a == b,
+            (Goal::Squares(a), Goal::Squares(b)) => a == b,
+            (Goal::Predicate(a), Goal::Predicate(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edge_goals() {
+        assert!(Goal::Edge(Edge::Top).contains((3, 8), 9, 9));
+        assert!(!Goal::Edge(Edge::Top).contains((3, 7), 9, 9));
+        assert!(Goal::Edge(Edge::Top).contains((3, 4), 9, 5));
+        assert!(Goal::Edge(Edge::Right).contains((8, 0), 9, 9));
+        assert!(Goal::Edge(Edge::Bottom).contains((0, 0), 9, 9));
+        assert!(Goal::Edge(Edge::Left).contains((0, 6), 9, 9));
+        assert!(!Goal::Edge(Edge::Left).contains((1, 6), 9, 9));
+    }
+
+    #[test]
+    fn square_goals() {
+        let goal = Goal::Squares(vec![(4, 4), (4, 5)]);
+        assert!(goal.contains((4, 4), 9, 9));
+        assert!(goal.contains((4, 5), 9, 9));
+        assert!(!goal.contains((5, 4), 9, 9));
+    }
+
+    #[test]
+    fn predicate_goals() {
+        let goal = Goal::Predicate(Arc::new(|(x, y)| x == y));
+        assert!(goal.contains((2, 2), 9, 9));
+        assert!(!goal.contains((2, 3), 9, 9));
+        assert_eq!(goal, goal.clone());
+        assert_ne!(goal, Goal::Predicate(Arc::new(|(x, y)| x == y)));
+    }
+
+    #[test]
+    fn opposite_edges() {
+        assert_eq!(Edge::Bottom, Edge::Top.opposite());
+        assert_eq!(Edge::Right, Edge::Left.opposite());
+    }
+}
@@ -0,0 +1,153 @@
+#![allow(dead_code)]
+
+//! A computer player that only looks one turn ahead, racing along its
+//! shortest path and walling off whoever is closest to winning.
+
+use crate::agent::Agent;
+use crate::board::Board;
+use crate::game::{Quoridor, Turn};
+use crate::path_finder::distance_to_goal;
+
+/// Each turn weighs up the best step along its own shortest path against the
+/// wall that slows the leading opponent down the most, and plays whichever
+/// gains more. Ties go to the step, so it never walls for nothing.
+pub struct GreedyBot;
+
+impl GreedyBot {
+    pub fn new() -> GreedyBot {
+        return GreedyBot;
+    }
+}
+
+impl Default for GreedyBot {
+    fn default() -> GreedyBot {
+        return GreedyBot::new();
+    }
+}
+
+/// How far a pawn is from its goal, with no way through counting as very far.
+fn distance(board: &Board, pawn: i8) -> i32 {
+    return distance_to_goal(board, pawn).unwrap_or(i32::MAX / 2);
+}
+
+/// The opponent of `player` who is closest to their goal.
+fn leading_opponent(game: &Quoridor, player: i32) -> Option<i8> {
+    return (0..game.number_of_players())
+        .filter(|opponent| *opponent != player)
+        .map(|opponent| opponent as i8)
+        .min_by_key(|opponent| distance(game.board(), *opponent));
+}
+
+/// The pawn move that gets the current player closest to their goal,
+/// with how many steps nearer it gets them.
+fn best_step(game: &Quoridor) -> Option<(Turn, i32)> {
+    let pawn = game.current_player() as i8;
+    let board = game.board();
+    let before = distance(board, pawn);
+    return game
+        .legal_pawn_turns()
+        .filter_map(|turn| {
+            let destination = game.destination(turn)?;
+            let after = distance(&board.clone().set_pawn(pawn, destination), pawn);
+            return Some((turn, before - after));
+        })
+        .fold(None, |best, (turn, gain)| match best {
+            Some((_, best_gain)) if best_gain >= gain => best,
+            _ => Some((turn, gain)),
+        });
+}
+
+/// The wall that adds the most to the leading opponent's path, less what it
+/// adds to the current player's own.
+fn best_wall(game: &Quoridor) -> Option<(Turn, i32)> {
+    let player = game.current_player();
+    let pawn = player as i8;
+    let opponent = leading_opponent(game, player)?;
+    let board = game.board();
+    let (own, theirs) = (distance(board, pawn), distance(board, opponent));
+    return game
+        .legal_walls()
+        .filter_map(|turn| match turn {
+            Turn::PlaceWall(location, orientation) => {
+                let walled = board.clone().place_wall(location, orientation);
+                let slowed = distance(&walled, opponent) - theirs;
+                let gain = slowed - (distance(&walled, pawn) - own);
+                return Some((turn, gain));
+            }
+            _ => None,
+        })
+        .fold(None, |best, (turn, gain)| match best {
+            Some((_, best_gain)) if best_gain >= gain => best,
+            _ => Some((turn, gain)),
+        });
+}
+
+impl Agent for GreedyBot {
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+        return match (best_step(game), best_wall(game)) {
+            (Some((_, step_gain)), Some((wall, wall_gain))) if wall_gain > step_gain => wall,
+            (Some((step, _)), _) => step,
+            (None, Some((wall, _))) => wall,
+            // Boxed in by other pawns with no walls left
+            (None, None) => Turn::Pass,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::board::{Direction::*, Orientation};
+    use crate::game::GameStatus;
+    use crate::random_bot::RandomBot;
+
+    #[test]
+    fn steps_towards_goal() {
+        let game = Quoridor::new_two_player();
+        assert_eq!(Turn::MovePawn(Up), GreedyBot::new().choose_turn(&game));
+    }
+
+    #[test]
+    fn steps_around_walls() {
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((3, 1), Orientation::Horizontal)).unwrap();
+        // The wall covers e2 to e3 and d2 to d3, so the short way round is right
+        assert_eq!(Turn::MovePawn(Right), GreedyBot::new().choose_turn(&game));
+    }
+
+    #[test]
+    fn walls_off_a_pocket() {
+        // Player 2 on a4 has to go round the wall below, through c4
+        let game = Quoridor::from_position_string("9x9 i1,a4 a3h 10,9 1").unwrap();
+        let turn = GreedyBot::new().choose_turn(&game);
+        assert!(matches!(turn, Turn::PlaceWall(..)), "{:?}", turn);
+        let walled = match turn {
+            Turn::PlaceWall(location, orientation) => {
+                game.board().clone().place_wall(location, orientation)
+            }
+            _ => unreachable!(),
+        };
+        assert!(distance(&walled, 1) >= distance(game.board(), 1) + 2);
+    }
+
+    #[test]
+    fn beats_random_bot() {
+        for seed in 0..3 {
+            let mut game = Quoridor::new_two_player();
+            let mut agents: Vec<Box<dyn Agent>> =
+                vec![Box::new(GreedyBot::new()), Box::new(RandomBot::new(seed))];
+            assert_eq!(GameStatus::Won { player: 0 }, play_game(&mut game, &mut agents, |_| ()));
+        }
+    }
+
+    #[test]
+    fn finishes_four_player_games() {
+        let mut game = Quoridor::new_four_player();
+        let mut agents: Vec<Box<dyn Agent>> = (0..4)
+            .map(|_| Box::new(GreedyBot::new()) as Box<dyn Agent>)
+            .collect();
+        assert_ne!(GameStatus::InProgress, play_game(&mut game, &mut agents, |_| ()));
+    }
+}
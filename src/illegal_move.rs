@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// Why a turn can't be played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IllegalMove {
+    /// The pawn or wall would end up off the edge of the board
+    OffBoard,
+    /// A wall is in the way of the pawn
+    BlockedByWall,
+    /// Another pawn is standing where the pawn would land
+    BlockedByPawn,
+    /// A diagonal jump with no pawn to jump over,
+    /// or when a straight jump is possible
+    InvalidJump,
+    /// The wall would lie on top of part of another wall
+    OverlappingWall,
+    /// The wall would cross another wall at its centre
+    CrossingWall,
+    NoWallsLeft,
+    /// The wall would leave a player with no way to their goal
+    WouldSealOff { player: i32 },
+    GameOver,
+    NotYourTurn,
+    /// Passing when there is a pawn move or wall to play
+    CantPass,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            IllegalMove::OffBoard => write!(f, "that would be off the board"),
+            IllegalMove::BlockedByWall => write!(f, "a wall is in the way"),
+            IllegalMove::BlockedByPawn => write!(f, "a pawn is in the way"),
+            IllegalMove::InvalidJump => write!(f, "that jump is not allowed"),
+            IllegalMove::OverlappingWall => write!(f, "that wall overlaps another wall"),
+            IllegalMove::CrossingWall => write!(f, "that wall crosses another wall"),
+            IllegalMove::NoWallsLeft => write!(f, "you have no walls left"),
+            IllegalMove::WouldSealOff { player } => write!(
+                f,
+                "that wall would stop player {} reaching their goal",
+                player + 1
+            ),
+            IllegalMove::GameOver => write!(f, "the game is already over"),
+            IllegalMove::NotYourTurn => write!(f, "it is not your turn"),
+            IllegalMove::CantPass => write!(f, "you can only pass with nothing else to play"),
+        };
+    }
+}
@@ -1,16 +1,93 @@
 #![crate_name = "quoridor"]
+// Explicit returns are the house style
+#![allow(clippy::needless_return)]
+mod agent;
 mod board;
 mod game;
+mod game_tree;
+mod goal;
+mod greedy_bot;
+mod illegal_move;
+mod mcts;
+mod negamax;
+mod notation;
 mod path_finder;
-use dialoguer::{theme::ColorfulTheme, Input, Select};
-use game::{Quoridor, Turn, Turn::*};
+mod random_bot;
+mod record;
+mod rules;
+mod save;
+mod zobrist;
+use agent::{Action, Agent};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use game::{GameStatus, Quoridor, Turn, Turn::*};
 
-use std::{io::Write, process::Command};
+use crate::board::Orientation;
+use crate::illegal_move::IllegalMove;
 
-use crate::board::{Direction, Direction::*, Orientation};
+const LAST_GAME: &str = "last_game.sav";
+
+/// Enough for the Monte Carlo bot to play sensibly without a long wait
+const BOT_ITERATIONS: u32 = 300;
 
 fn main() {
-    start_game();
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(game) = load_argument(&args, "--replay") {
+        replay_game(&game);
+        return;
+    }
+    let game = load_argument(&args, "--load")
+        .or_else(resume_game)
+        .unwrap_or_else(choose_game);
+    let seed = seed_argument(&args);
+    let iterations = iterations_argument(&args);
+    let agents = choose_agents(&game, seed, iterations);
+    start_game(game, agents);
+}
+
+/// The seed given with `--seed` on the command line, so games against bots
+/// can be played again, or one from the clock. Exits if it isn't a number.
+fn seed_argument(args: &[String]) -> u64 {
+    let index = match args.iter().position(|arg| arg == "--seed") {
+        Some(index) => index,
+        None => {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+            return now.map(|time| time.as_nanos() as u64).unwrap_or(0);
+        }
+    };
+    let seed = args.get(index + 1).and_then(|seed| seed.parse().ok());
+    return seed.unwrap_or_else(|| {
+        eprintln!("--seed needs a number");
+        std::process::exit(1);
+    });
+}
+
+/// How many games the Monte Carlo bot plays out each turn, given with
+/// `--iterations` on the command line. Exits if it isn't a number.
+fn iterations_argument(args: &[String]) -> u32 {
+    let index = match args.iter().position(|arg| arg == "--iterations") {
+        Some(index) => index,
+        None => return BOT_ITERATIONS,
+    };
+    let iterations = args.get(index + 1).and_then(|iterations| iterations.parse().ok());
+    return iterations.unwrap_or_else(|| {
+        eprintln!("--iterations needs a number");
+        std::process::exit(1);
+    });
+}
+
+/// Loads the file named after `flag` on the command line, if it is there.
+/// Exits if the file can't be loaded.
+fn load_argument(args: &[String], flag: &str) -> Option<Quoridor> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    let file_name = args.get(index + 1).unwrap_or_else(|| {
+        eprintln!("{} needs a file to load", flag);
+        std::process::exit(1);
+    });
+    let game = new_from_file(file_name).unwrap_or_else(|reason| {
+        eprintln!("Couldn't load {}: {}", file_name, reason);
+        std::process::exit(1);
+    });
+    return Some(game);
 }
 
 fn clear_screen() {
@@ -18,42 +95,201 @@ fn clear_screen() {
     //assert!(Command::new("clear").status().unwrap().success());
 }
 
-fn start_game() {
-    let mut game = Quoridor::new_two_player();
-    while !game.has_won() {
-        let turn = get_turn(&mut game);
-        if game.is_valid(turn) {
-            game.play(turn);
+/// Offers to carry on with the autosaved game, if it isn't finished.
+fn resume_game() -> Option<Quoridor> {
+    let game = new_from_file(LAST_GAME).ok().filter(|game| !game.is_over())?;
+    let resume = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Resume unfinished game?")
+        .default(true)
+        .interact_opt()
+        .unwrap();
+    return match resume {
+        Some(true) => Some(game),
+        _ => None,
+    };
+}
+
+fn choose_game() -> Quoridor {
+    let items = ["Two players", "Four players"];
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("How many players?")
+        .default(0)
+        .items(&items[..])
+        .interact_opt()
+        .unwrap();
+
+    return match selection {
+        Some(1) => Quoridor::new_four_player(),
+        _ => Quoridor::new_two_player(),
+    };
+}
+
+/// Saves the game so it can be resumed, carrying on without it if that fails.
+fn autosave(game: &Quoridor) {
+    if let Err(reason) = save::save_file(game, LAST_GAME) {
+        println!("Couldn't save the game: {}", reason);
+    }
+}
+
+/// Someone choosing turns at the terminal.
+struct Human;
+
+impl Agent for Human {
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+        loop {
+            if let Some(Action::Play(turn)) = get_action(game) {
+                return turn;
+            }
+        }
+    }
+
+    fn choose_action(&mut self, game: &Quoridor) -> Action {
+        loop {
+            if let Some(action) = get_action(game) {
+                return action;
+            }
+        }
+    }
+
+    fn refused(&mut self, game: &Quoridor, _turn: Turn, reason: IllegalMove) {
+        invalid_input(game, reason.to_string());
+    }
+
+    fn is_interactive(&self) -> bool {
+        return true;
+    }
+}
+
+/// Asks who plays each player, a person or a bot. Bots get seeds following on
+/// from `seed`, the Monte Carlo bot plays out `iterations` games a turn.
+/// The hard bot only plays two player games.
+fn choose_agents(game: &Quoridor, seed: u64, iterations: u32) -> Vec<Box<dyn Agent>> {
+    let mut items = vec!["Human", "Random bot", "Greedy bot", "Monte Carlo bot"];
+    if game.number_of_players() == 2 {
+        items.push("Hard bot");
+    }
+    let mut agents: Vec<Box<dyn Agent>> = Vec::new();
+    for player in 0..game.number_of_players() {
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Who plays player {}?", player + 1))
+            .default(0)
+            .items(&items[..])
+            .interact_opt()
+            .unwrap();
+        let bot_seed = seed.wrapping_add(player as u64);
+        agents.push(match selection {
+            Some(1) => Box::new(random_bot::RandomBot::new(bot_seed)),
+            Some(2) => Box::new(greedy_bot::GreedyBot::new()),
+            Some(3) => Box::new(mcts::Mcts::new(bot_seed).set_iterations(iterations)),
+            Some(4) => Box::new(negamax::NegamaxBot::new()),
+            _ => Box::new(Human),
+        });
+    }
+    return agents;
+}
+
+fn start_game(mut game: Quoridor, mut agents: Vec<Box<dyn Agent>>) {
+    agent::play_game(&mut game, &mut agents, autosave);
+    autosave(&game);
+    announce_result(&game);
+
+    let replay = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Watch a replay?")
+        .default(false)
+        .interact_opt()
+        .unwrap();
+    if replay == Some(true) {
+        replay_game(&game);
+    }
+}
+
+/// Steps forwards and backwards through a game from the start,
+/// showing each position and the turn that led to it.
+fn replay_game(game: &Quoridor) {
+    let turns = game.history_to_notation();
+    let mut replay = game.rewound();
+    loop {
+        clear_screen();
+        println!("Replay");
+        print!("{}", replay);
+        let shown = replay.history().len();
+        if shown == 0 {
+            println!("Start of the game, {} turns to go", turns.len());
         } else {
-            invalid_input(&game);
+            let players = replay.number_of_players();
+            let player = (replay.current_player() + players - 1) % players;
+            println!(
+                "Turn {} of {}: player {} played {}",
+                shown,
+                turns.len(),
+                player + 1,
+                turns[shown - 1]
+            );
+        }
+
+        let items = ["Next", "Previous", "Go to turn", "Quit"];
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Where to?")
+            .clear(true)
+            .default(0)
+            .items(&items[..])
+            .interact_opt()
+            .unwrap();
+        match selection {
+            Some(0) => {
+                replay.redo();
+            }
+            Some(1) => {
+                replay.undo();
+            }
+            Some(2) => {
+                let target = choose_turn_number(turns.len());
+                while replay.history().len() > target && replay.undo().is_some() {}
+                while replay.history().len() < target && replay.redo().is_some() {}
+            }
+            _ => return,
         }
     }
-    println!("Well done some one won");
 }
 
-fn new_from_file(file_name: String) -> game::Quoridor {
-    let result = Quoridor::new_two_player();
-    std::fs::OpenOptions::new()
-        .read(true)
-        .write(false)
-        .open(format!("saves/{}", file_name))
-        .err();
+fn choose_turn_number(turns: usize) -> usize {
+    let selection: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Go to turn (0 to {})", turns))
+        .validate_with(|in_text: &String| -> Result<(), &str> {
+            match in_text.trim().parse::<usize>() {
+                Ok(turn) if turn <= turns => Ok(()),
+                Ok(_) => Err("there aren't that many turns"),
+                Err(_) => Err("not a number"),
+            }
+        })
+        .interact_text()
+        .unwrap();
+    return selection.trim().parse().unwrap();
+}
 
-    return result;
-    
+fn announce_result(game: &game::Quoridor) {
+    print!("{}", game);
+    match game.status() {
+        GameStatus::Won { player } => println!("Well done player {}, you won!", player + 1),
+        GameStatus::Resigned { player } => println!("Player {} resigned", player + 1),
+        GameStatus::DrawAgreed => println!("The game is drawn by agreement"),
+        GameStatus::DrawByRule => println!("The game is drawn, the turn limit was reached"),
+        GameStatus::InProgress => (),
+    }
 }
 
-fn get_turn(game: &game::Quoridor) -> Turn {
+fn new_from_file(file_name: &str) -> Result<Quoridor, save::LoadError> {
+    return save::load_file(file_name);
+}
+
+fn get_action(game: &game::Quoridor) -> Option<Action> {
     clear_screen();
-    //println!("{}", game.history_to_str());
-    dbg!("Before");
-    std::fs::File::create("last_game.sav").unwrap();
-    dbg!("After");
-    let mut save_file = std::fs::OpenOptions::new().append(false).write(true).open("last_game.sav").expect("Couldn't Save game");
-    save_file.write(game.history_to_str().as_bytes()).unwrap();
     println!("Quoridor Game");
-    print!("{}", game.to_string());
-    let items = vec!["Move Pawn", "Place Wall"];
+    print!("{}", game);
+    for player in game.draw_offers() {
+        println!("Player {} offers a draw", player + 1);
+    }
+    let items = ["Move Pawn", "Place Wall", "Type a move", "Undo", "Offer draw", "Resign"];
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("What would you like to do?")
         .clear(true)
@@ -62,28 +298,39 @@ fn get_turn(game: &game::Quoridor) -> Turn {
         .interact_opt()
         .unwrap();
 
-    let turn: Turn;
-
-    turn = match selection {
-        Some(0) => move_pawn(game),
-        Some(1) => place_wall(game),
-        _ => invalid_input(game),
+    let action = match selection {
+        Some(0) => move_pawn(game).map(Action::Play),
+        Some(1) => place_wall(game).map(Action::Play),
+        Some(2) => type_turn(game).map(Action::Play),
+        Some(3) => Some(Action::Undo),
+        Some(4) => Some(Action::OfferDraw),
+        Some(5) => Some(Action::Resign),
+        _ => {
+            invalid_input(game, String::from("nothing was selected"));
+            None
+        }
     };
 
-    return turn;
+    return action;
 }
 
-fn move_pawn(game: &game::Quoridor) -> Turn {
-    let directions: Vec<Direction> = vec![Up, Right, Down, Left]
-        .iter()
-        .filter(|x| game.can_move(**x))
-        .map(|x| *x)
+fn move_pawn(game: &game::Quoridor) -> Option<Turn> {
+    let turns: Vec<Turn> = game
+        .legal_turns()
+        .filter(|turn| !matches!(turn, PlaceWall(..)))
         .collect();
 
-    let items: Vec<String> = directions
-        .to_owned()
+    let items: Vec<String> = turns
         .iter()
-        .map(|x| format!("{:?}", (x)))
+        .map(|x| {
+            let square = x.to_notation(game).unwrap();
+            match x {
+                JumpPawn(direction, side) => format!("{} ({:?} then {:?})", square, direction, side),
+                MovePawn(direction) => format!("{} ({:?})", square, direction),
+                Pass => String::from("Pass, there is nowhere to go"),
+                PlaceWall(..) => unreachable!(),
+            }
+        })
         .collect();
 
     let selection = Select::with_theme(&ColorfulTheme::default())
@@ -95,17 +342,19 @@ fn move_pawn(game: &game::Quoridor) -> Turn {
         .unwrap();
 
     if let Some(selection) = selection {
-        if selection >= directions.len() {
-            invalid_input(game);
+        if selection >= turns.len() {
+            invalid_input(game, String::from("no such move"));
+            return None;
         }
 
-        return MovePawn(directions[selection]);
+        return Some(turns[selection]);
     } else {
-        return invalid_input(game);
+        invalid_input(game, String::from("no move was selected"));
+        return None;
     }
 }
 
-fn place_wall(game: &game::Quoridor) -> Turn {
+fn place_wall(game: &game::Quoridor) -> Option<Turn> {
     let direction_choice = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Which direction?")
         .items(&["Vertical", "Horizontal", "back"])
@@ -117,42 +366,48 @@ fn place_wall(game: &game::Quoridor) -> Turn {
     match direction_choice {
         Some(0) => direction = Orientation::Vertical,
         Some(1) => direction = Orientation::Horizontal,
-        Some(2) => return get_turn(game),
+        Some(2) => return None,
         _ => (),
     }
 
+    let (width, height) = (game.rules().get_width(), game.rules().get_height());
     let selection: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select Location")
+        .with_prompt("Select Location (e.g. e3)")
         .validate_with(|in_text: &String| -> Result<(), &str> {
-            let input = in_text.to_ascii_uppercase();
-            if input.len() != 2 {
-                return Err("invalid Length");
+            match notation::parse_square(in_text.trim()) {
+                Some((x, y)) if x < width - 1 && y < height - 1 => Ok(()),
+                Some(_) => Err("walls can't go on the top row or right column"),
+                None => Err("not a square"),
             }
-            if !(input.chars().nth(0).unwrap() >= 'A' && input.chars().nth(0).unwrap() <= 'I') {
-                return Err("invalid first char");
-            }
-            if !(input.chars().nth(1).unwrap() >= '1' && input.chars().nth(1).unwrap() <= '9') {
-                return Err("invalid second char");
-            }
-            Ok(())
         })
         .interact_text()
-        .unwrap()
-        .to_ascii_uppercase();
+        .unwrap();
+
+    let (col, row) = notation::parse_square(selection.trim()).unwrap();
+    return Some(PlaceWall((col, row), direction));
+}
+
+fn type_turn(game: &game::Quoridor) -> Option<Turn> {
+    let text: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Move (e.g. e2 or e3h)")
+        .interact_text()
+        .unwrap();
 
-    let col: i32 = selection.chars().nth(0).unwrap() as i32 - 'A' as i32;
-    let row: i32 = selection.chars().nth(1).unwrap() as i32 - '1' as i32;
-    return PlaceWall((col, row), direction);
+    return match Turn::from_notation(&text, game) {
+        Ok(turn) => Some(turn),
+        Err(reason) => {
+            invalid_input(game, reason.to_string());
+            None
+        }
+    };
 }
 
-fn invalid_input(_game: &game::Quoridor) -> Turn {
-    let message: String = String::from("Invalid input: "); // + reason.as_str();
+fn invalid_input(_game: &game::Quoridor, reason: String) {
+    let message: String = String::from("Invalid input: ") + reason.as_str();
     let items = vec!["Conintue"];
     let _wait_for_input = dialoguer::Select::with_theme(&ColorfulTheme::default())
         .with_prompt(message)
         .items(&items)
         .interact()
         .unwrap();
-    // TODO add a invalid turn type that does nothing.
-    return PlaceWall((-1, -1), Orientation::Vertical);
 }
@@ -0,0 +1,381 @@
+#![allow(dead_code)]
+
+//! A computer player using Monte Carlo tree search with UCT.
+//!
+//! Rather than looking at every turn to a fixed depth, it plays lots of quick
+//! games from the position and spends more of them on the turns that have
+//! been doing well, which copes better with the hundred or so walls that
+//! could be placed each turn.
+
+use crate::agent::Agent;
+use crate::board::{shift, Direction, Orientation};
+use crate::game::{GameStatus, Quoridor, Turn};
+use crate::path_finder::{distance_to_goal, path_to_goal};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// How the quick games played out from each new position pick their turns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Playout {
+    /// Any legal turn
+    Random,
+    /// Mostly a step along the pawn's shortest path, sometimes any legal turn
+    ShortestPath,
+}
+
+/// How often a shortest path playout steps along the path.
+const PATH_STEP_CHANCE: f64 = 0.8;
+
+/// How many turns a random playout draws before listing the legal ones.
+const RANDOM_TURN_TRIES: u32 = 20;
+
+/// How one of the turns from the searched position did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub turn: Turn,
+    /// How many of the search's games started with this turn
+    pub visits: u32,
+    /// The share of those games the player to move won, from 0 to 1
+    pub win_rate: f64,
+}
+
+struct Node {
+    turn: Option<Turn>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Turns that don't have a node yet
+    untried: Vec<Turn>,
+    /// The player who played `turn`, whose wins are counted here
+    player: i32,
+    visits: u32,
+    wins: f64,
+}
+
+/// Searches by playing games out. The same seed always makes the same choices.
+pub struct Mcts {
+    iterations: u32,
+    exploration: f64,
+    playout: Playout,
+    playout_limit: u32,
+    rng: ChaCha8Rng,
+}
+
+impl Mcts {
+    pub fn new(seed: u64) -> Mcts {
+        return Mcts {
+            iterations: 1000,
+            exploration: std::f64::consts::SQRT_2,
+            playout: Playout::ShortestPath,
+            playout_limit: 60,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+    }
+
+    /// How many games to play out for each turn chosen.
+    pub fn set_iterations(mut self, iterations: u32) -> Mcts {
+        self.iterations = iterations;
+        return self;
+    }
+
+    pub fn get_iterations(&self) -> u32 {
+        return self.iterations;
+    }
+
+    /// How much to favour turns that have been tried less over ones that
+    /// have been winning. The usual UCT value is the square root of 2.
+    pub fn set_exploration(mut self, exploration: f64) -> Mcts {
+        self.exploration = exploration;
+        return self;
+    }
+
+    pub fn get_exploration(&self) -> f64 {
+        return self.exploration;
+    }
+
+    pub fn set_playout(mut self, playout: Playout) -> Mcts {
+        self.playout = playout;
+        return self;
+    }
+
+    pub fn get_playout(&self) -> Playout {
+        return self.playout;
+    }
+
+    /// How many turns a playout goes on for before the pawn nearest its goal
+    /// is taken to have won.
+    pub fn set_playout_limit(mut self, playout_limit: u32) -> Mcts {
+        self.playout_limit = playout_limit;
+        return self;
+    }
+
+    pub fn get_playout_limit(&self) -> u32 {
+        return self.playout_limit;
+    }
+
+    /// Searches the position, giving every turn from it that was tried,
+    /// most visited first. Empty if the game is over.
+    pub fn search(&mut self, game: &Quoridor) -> Vec<Candidate> {
+        if game.is_over() {
+            return Vec::new();
+        }
+        let mut nodes = vec![Node {
+            turn: None,
+            parent: None,
+            children: Vec::new(),
+            untried: game.legal_turns().collect(),
+            player: -1,
+            visits: 0,
+            wins: 0.0,
+        }];
+        for _ in 0..self.iterations {
+            let mut position = game.clone();
+            let leaf = self.select_and_expand(&mut nodes, &mut position);
+            let rewards = self.play_out(&mut position);
+            let mut node = Some(leaf);
+            while let Some(index) = node {
+                let player = nodes[index].player;
+                nodes[index].visits += 1;
+                if player >= 0 {
+                    nodes[index].wins += rewards[player as usize];
+                }
+                node = nodes[index].parent;
+            }
+        }
+
+        let mut candidates: Vec<Candidate> = nodes[0]
+            .children
+            .iter()
+            .map(|child| {
+                let node = &nodes[*child];
+                return Candidate {
+                    turn: node.turn.unwrap(),
+                    visits: node.visits,
+                    win_rate: node.wins / node.visits as f64,
+                };
+            })
+            .collect();
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.visits));
+        return candidates;
+    }
+
+    /// Walks down the tree by UCT, playing the turns on `position`, and adds
+    /// a node for one untried turn at the end. Returns the last node.
+    fn select_and_expand(&mut self, nodes: &mut Vec<Node>, position: &mut Quoridor) -> usize {
+        let mut current = 0;
+        while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+            let parent_visits = nodes[current].visits as f64;
+            let uct = |child: &Node| {
+                let visits = child.visits as f64;
+                return child.wins / visits
+                    + self.exploration * (parent_visits.ln() / visits).sqrt();
+            };
+            let mut best = nodes[current].children[0];
+            for child in &nodes[current].children {
+                if uct(&nodes[*child]) > uct(&nodes[best]) {
+                    best = *child;
+                }
+            }
+            position.play(nodes[best].turn.unwrap()).unwrap();
+            current = best;
+        }
+
+        if nodes[current].untried.is_empty() {
+            return current;
+        }
+        let choice = self.rng.gen_range(0..nodes[current].untried.len());
+        let turn = nodes[current].untried.swap_remove(choice);
+        let player = position.current_player();
+        position.play(turn).unwrap();
+        nodes.push(Node {
+            turn: Some(turn),
+            parent: Some(current),
+            children: Vec::new(),
+            untried: position.legal_turns().collect(),
+            player,
+            visits: 0,
+            wins: 0.0,
+        });
+        let child = nodes.len() - 1;
+        nodes[current].children.push(child);
+        return child;
+    }
+
+    /// Plays the game out, giving each player's share of the win.
+    fn play_out(&mut self, position: &mut Quoridor) -> Vec<f64> {
+        for _ in 0..self.playout_limit {
+            if position.is_over() {
+                break;
+            }
+            let turn = self.playout_turn(position);
+            position.play(turn).unwrap();
+        }
+
+        let players = position.number_of_players() as usize;
+        let mut rewards = vec![0.0; players];
+        match position.status() {
+            GameStatus::Won { player } => rewards[player as usize] = 1.0,
+            GameStatus::InProgress => {
+                // Whoever is nearest their goal is taken to have won
+                let distances: Vec<i32> = (0..players)
+                    .map(|pawn| distance_to_goal(position.board(), pawn as i8).unwrap_or(i32::MAX))
+                    .collect();
+                let nearest = *distances.iter().min().unwrap();
+                let leaders = distances.iter().filter(|distance| **distance == nearest).count();
+                for (reward, distance) in rewards.iter_mut().zip(distances) {
+                    if distance == nearest {
+                        *reward = 1.0 / leaders as f64;
+                    }
+                }
+            }
+            _ => rewards = vec![1.0 / players as f64; players],
+        }
+        return rewards;
+    }
+
+    fn playout_turn(&mut self, position: &Quoridor) -> Turn {
+        if self.playout == Playout::ShortestPath && self.rng.gen_bool(PATH_STEP_CHANCE) {
+            let pawn = position.current_player() as i8;
+            let step = path_to_goal(position.board(), pawn).and_then(|path| {
+                let direction = [Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+                    .iter()
+                    .find(|direction| shift(&path[0], **direction) == path[1])?;
+                return Some(Turn::MovePawn(*direction));
+            });
+            if let Some(step) = step.filter(|step| position.is_valid(*step)) {
+                return step;
+            }
+        }
+        return self.random_turn(position);
+    }
+
+    /// Any legal turn, all equally likely. Listing the legal turns means
+    /// checking every wall for sealing someone off, so instead turns are
+    /// drawn from every pawn move and wall slot until one is legal.
+    fn random_turn(&mut self, position: &Quoridor) -> Turn {
+        let pawn_turns: Vec<Turn> = position.pawn_turns().collect();
+        let columns = position.board().get_width() - 1;
+        let rows = position.board().get_height() - 1;
+        let walls = if position.walls_left(position.current_player()) > 0 {
+            columns * rows * 2
+        } else {
+            0
+        };
+        for _ in 0..RANDOM_TURN_TRIES {
+            let choice = self.rng.gen_range(0..pawn_turns.len() + walls as usize);
+            let turn = match pawn_turns.get(choice) {
+                Some(turn) => *turn,
+                None => {
+                    let wall = (choice - pawn_turns.len()) as i32;
+                    let slot = wall / 2;
+                    let orientation = if wall % 2 == 0 {
+                        Orientation::Horizontal
+                    } else {
+                        Orientation::Vertical
+                    };
+                    Turn::PlaceWall((slot % columns, slot / columns), orientation)
+                }
+            };
+            if position.is_valid(turn) {
+                return turn;
+            }
+        }
+        // So few turns are legal that listing them is quicker, and a pawn
+        // that is boxed in can only pass
+        let turns: Vec<Turn> = position.legal_turns().collect();
+        return turns[self.rng.gen_range(0..turns.len())];
+    }
+}
+
+impl Agent for Mcts {
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+        return self.search(game).first().expect("The game is over").turn;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::Direction::*;
+
+    #[test]
+    fn same_seed_same_search() {
+        let game = Quoridor::new_two_player();
+        let mut first = Mcts::new(3).set_iterations(100);
+        let mut second = Mcts::new(3).set_iterations(100);
+        assert_eq!(first.search(&game), second.search(&game));
+    }
+
+    #[test]
+    fn every_iteration_visits_a_turn() {
+        let game = Quoridor::new_two_player();
+        let candidates = Mcts::new(0).set_iterations(140).search(&game);
+        assert_eq!(131, candidates.len());
+        assert_eq!(140, candidates.iter().map(|candidate| candidate.visits).sum::<u32>());
+        for pair in candidates.windows(2) {
+            assert!(pair[0].visits >= pair[1].visits);
+        }
+    }
+
+    #[test]
+    fn takes_a_win() {
+        for playout in &[Playout::Random, Playout::ShortestPath] {
+            let game = Quoridor::from_position_string("9x9 e8,a5 - 0,0 1").unwrap();
+            let mut mcts = Mcts::new(1).set_iterations(100).set_playout(*playout);
+            let best = &mcts.search(&game)[0];
+            assert_eq!(Turn::MovePawn(Up), best.turn);
+            assert_eq!(1.0, best.win_rate);
+        }
+    }
+
+    #[test]
+    fn nothing_to_search_when_over() {
+        let mut game = Quoridor::new_two_player();
+        game.resign(0).unwrap();
+        assert!(Mcts::new(0).search(&game).is_empty());
+    }
+
+    #[test]
+    fn plays_four_player_games() {
+        let game = Quoridor::new_four_player();
+        let candidates = Mcts::new(0).set_iterations(50).search(&game);
+        assert_eq!(50, candidates.iter().map(|candidate| candidate.visits).sum::<u32>());
+    }
+
+    #[test]
+    fn random_turns_are_legal() {
+        let game = Quoridor::from_position_string("5x5 b2,c4 b2h,c3v 1,1 1").unwrap();
+        let mut mcts = Mcts::new(0);
+        let mut seen = Vec::new();
+        for _ in 0..500 {
+            let turn = mcts.random_turn(&game);
+            assert!(game.is_valid(turn), "{:?}", turn);
+            if !seen.contains(&turn) {
+                seen.push(turn);
+            }
+        }
+        assert_eq!(game.legal_turn_count(), seen.len());
+    }
+
+    #[test]
+    fn playouts_pass_when_boxed_in() {
+        // Player 1 on a1 can't move, walled in above with player 2 on b1 and
+        // player 4 behind it
+        let position = Quoridor::from_position_string("9x9 a1,b1,e9,c1 a1h 0,5,5,5 1").unwrap();
+        let mut mcts = Mcts::new(0);
+        for _ in 0..10 {
+            assert_eq!(Turn::Pass, mcts.playout_turn(&position));
+        }
+        let candidates = mcts.set_iterations(20).search(&position);
+        assert_eq!(vec![Turn::Pass], candidates.iter().map(|c| c.turn).collect::<Vec<Turn>>());
+    }
+
+    #[test]
+    fn stops_the_opponent_winning() {
+        // Player 2 on a2 wins next turn unless the wall under a2 and b2 goes
+        // down, after which player 1 on e7 is nearer
+        let game = Quoridor::from_position_string("9x9 e7,a2 - 10,10 1").unwrap();
+        let mut mcts = Mcts::new(0).set_iterations(500);
+        let best = &mcts.search(&game)[0];
+        assert_eq!(Turn::PlaceWall((0, 0), Orientation::Horizontal), best.turn);
+    }
+}
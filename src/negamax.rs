@@ -0,0 +1,238 @@
+#![allow(dead_code)]
+
+//! A computer player that searches every turn a few turns deep, using
+//! negamax with alpha-beta pruning. Two player games only.
+
+use crate::agent::Agent;
+use crate::game::{GameStatus, Quoridor, Turn};
+use crate::path_finder::distance_to_goal;
+
+/// The score for having won. Wins found sooner score a little higher.
+pub const WIN: i32 = 1_000_000;
+
+/// How much a step nearer the goal is worth compared to a wall in hand.
+const STEP_VALUE: i32 = 3;
+
+/// What a search found, all from the point of view of the player to move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub turn: Turn,
+    pub score: i32,
+    /// The turns both sides are expected to play, starting with `turn`
+    pub principal_variation: Vec<Turn>,
+}
+
+/// Scores a position for the player to move, without looking ahead.
+///
+/// The opponent's shortest path less the player's own, plus the walls the
+/// player has in hand less the opponent's. A finished game scores `WIN` for
+/// the winner and 0 for a draw.
+pub fn evaluate(game: &Quoridor) -> i32 {
+    return score(game, 0);
+}
+
+fn score(game: &Quoridor, ply: i32) -> i32 {
+    let player = game.current_player();
+    return match game.status() {
+        GameStatus::Won { player: winner } if winner == player => WIN - ply,
+        GameStatus::Won { .. } => -(WIN - ply),
+        GameStatus::Resigned { player: loser } if loser == player => -(WIN - ply),
+        GameStatus::Resigned { .. } => WIN - ply,
+        GameStatus::DrawAgreed | GameStatus::DrawByRule => 0,
+        GameStatus::InProgress => {
+            let opponent = 1 - player;
+            let distance = |player: i32| {
+                return distance_to_goal(game.board(), player as i8).unwrap_or(WIN / 2);
+            };
+            let paths = distance(opponent) - distance(player);
+            let walls = game.walls_left(player) - game.walls_left(opponent);
+            paths * STEP_VALUE + walls
+        }
+    };
+}
+
+/// Searches `depth` turns ahead for the best turn for the player to move.
+/// `None` if the game is over or the player has no legal turn.
+pub fn search(game: &Quoridor, depth: u32) -> Option<SearchResult> {
+    if game.number_of_players() != 2 {
+        panic!("Negamax only plays two player games");
+    }
+    if game.is_over() {
+        return None;
+    }
+    let mut game = game.clone();
+    let (score, principal_variation) = negamax(&mut game, depth.max(1), -WIN - 1, WIN + 1, 0);
+    return Some(SearchResult {
+        turn: *principal_variation.first()?,
+        score,
+        principal_variation,
+    });
+}
+
+/// The score of the position for the player to move and the turns leading
+/// to it. Turns that can't be better than `alpha` or worse than `beta` for
+/// the player aren't looked into further.
+fn negamax(
+    game: &mut Quoridor,
+    depth: u32,
+    mut alpha: i32,
+    beta: i32,
+    ply: i32,
+) -> (i32, Vec<Turn>) {
+    if depth == 0 || game.is_over() {
+        return (score(game, ply), Vec::new());
+    }
+    let turns: Vec<Turn> = game.legal_turns().collect();
+    let mut best = (-WIN - 1, Vec::new());
+    for turn in turns {
+        game.play(turn).unwrap();
+        let (reply_score, replies) = negamax(game, depth - 1, -beta, -alpha, ply + 1);
+        game.undo();
+        if -reply_score > best.0 {
+            let mut line = vec![turn];
+            line.extend(replies);
+            best = (-reply_score, line);
+        }
+        alpha = alpha.max(best.0);
+        if alpha >= beta {
+            break;
+        }
+    }
+    return best;
+}
+
+/// Plays the first turn of the best line it can find.
+pub struct NegamaxBot {
+    depth: u32,
+}
+
+impl NegamaxBot {
+    pub fn new() -> NegamaxBot {
+        return NegamaxBot { depth: 2 };
+    }
+
+    /// How many turns ahead to look, counting both players' turns.
+    pub fn set_depth(mut self, depth: u32) -> NegamaxBot {
+        self.depth = depth;
+        return self;
+    }
+
+    pub fn get_depth(&self) -> u32 {
+        return self.depth;
+    }
+}
+
+impl Default for NegamaxBot {
+    fn default() -> NegamaxBot {
+        return NegamaxBot::new();
+    }
+}
+
+impl Agent for NegamaxBot {
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+        return search(game, self.depth).expect("The game is over").turn;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::board::Direction::*;
+    use crate::greedy_bot::GreedyBot;
+
+    /// Negamax without pruning, to check the pruning doesn't change the score.
+    fn full_negamax(game: &mut Quoridor, depth: u32, ply: i32) -> i32 {
+        if depth == 0 || game.is_over() {
+            return score(game, ply);
+        }
+        let turns: Vec<Turn> = game.legal_turns().collect();
+        let mut best = -WIN - 1;
+        for turn in turns {
+            game.play(turn).unwrap();
+            best = best.max(-full_negamax(game, depth - 1, ply + 1));
+            game.undo();
+        }
+        return best;
+    }
+
+    #[test]
+    fn start_is_even() {
+        assert_eq!(0, evaluate(&Quoridor::new_two_player()));
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        assert_eq!(-STEP_VALUE, evaluate(&game));
+    }
+
+    #[test]
+    fn takes_a_win() {
+        let game = Quoridor::from_position_string("9x9 e8,a5 - 10,10 1").unwrap();
+        let result = search(&game, 3).unwrap();
+        assert_eq!(Turn::MovePawn(Up), result.turn);
+        assert_eq!(WIN - 1, result.score);
+        assert_eq!(vec![Turn::MovePawn(Up)], result.principal_variation);
+    }
+
+    #[test]
+    fn stops_the_opponent_winning() {
+        let game = Quoridor::from_position_string("9x9 e2,a2 - 10,10 1").unwrap();
+        let result = search(&game, 2).unwrap();
+        assert!(matches!(result.turn, Turn::PlaceWall(..)), "{:?}", result);
+        assert!(result.score > -WIN / 2);
+    }
+
+    #[test]
+    fn sees_a_lost_game() {
+        // Player 2 is a step from winning and player 1 has no walls to stop it
+        let game = Quoridor::from_position_string("9x9 e2,a2 - 0,10 1").unwrap();
+        let result = search(&game, 2).unwrap();
+        assert_eq!(-(WIN - 2), result.score);
+        assert_eq!(2, result.principal_variation.len());
+    }
+
+    #[test]
+    fn resigning_loses() {
+        // The player to move resigned, so every line ending here is a loss for them
+        let mut game = Quoridor::new_two_player();
+        game.resign(0).unwrap();
+        assert_eq!(-(WIN - 3), score(&game, 3));
+        assert_eq!(None, search(&game, 2));
+
+        let mut game = Quoridor::new_two_player();
+        game.resign(1).unwrap();
+        assert_eq!(WIN - 3, score(&game, 3));
+    }
+
+    #[test]
+    fn pruning_keeps_the_score() {
+        for position in &["5x5 c1,c5 - 1,1 1", "5x5 b2,c4 b2h,c3v 0,1 2"] {
+            let game = Quoridor::from_position_string(position).unwrap();
+            for depth in 1..=3 {
+                let result = search(&game, depth).unwrap();
+                assert_eq!(full_negamax(&mut game.clone(), depth, 0), result.score);
+                assert_eq!(depth as usize, result.principal_variation.len());
+                // Playing out the principal variation reaches the position scored
+                let mut line = game.clone();
+                for turn in &result.principal_variation {
+                    line.play(*turn).unwrap();
+                }
+                let sign = if depth % 2 == 0 { 1 } else { -1 };
+                assert_eq!(result.score, sign * score(&line, depth as i32));
+            }
+        }
+    }
+
+    #[test]
+    fn beats_greedy_bot() {
+        let mut game = Quoridor::new_two_player();
+        let mut agents: Vec<Box<dyn Agent>> =
+            vec![Box::new(GreedyBot::new()), Box::new(NegamaxBot::new())];
+        assert_eq!(GameStatus::Won { player: 1 }, play_game(&mut game, &mut agents, |_| ()));
+    }
+
+    #[test]
+    #[should_panic(expected = "two player")]
+    fn two_players_only() {
+        search(&Quoridor::new_four_player(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+#![allow(dead_code)]
+
+//! Standard Quoridor notation.
+//!
+//! Squares are a column letter and a row number, `a1` being the bottom left
+//! square. A pawn move is written as the square the pawn lands on, `e2`.
+//! A wall is written as the bottom left square it touches followed by `h` or
+//! `v`, so `e3h` runs along the top of `e3` and `f3` and `e3v` runs along the
+//! right of `e3` and `e4`.
+
+use crate::board::{Orientation, Point};
+use crate::game::{Quoridor, Turn};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotationError {
+    /// The text isn't a square or a wall
+    Malformed(String),
+    /// The current player's pawn can't get to the square this turn
+    Unreachable(Point),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            NotationError::Malformed(text) => write!(f, "\"{}\" is not a move", text),
+            NotationError::Unreachable(square) => {
+                write!(f, "can't move to {}", square_to_str(*square))
+            }
+        };
+    }
+}
+
+/// Writes a square like `e2`.
+pub fn square_to_str((x, y): Point) -> String {
+    return format!("{}{}", (b'a' + x as u8) as char, y + 1);
+}
+
+/// Reads a square like `e2`, in either case.
+pub fn parse_square(text: &str) -> Option<Point> {
+    let mut chars = text.chars();
+    let column = chars.next()?.to_ascii_lowercase();
+    if !column.is_ascii_lowercase() {
+        return None;
+    }
+    let row: i32 = chars.as_str().parse().ok()?;
+    if row < 1 || chars.as_str().starts_with('+') {
+        return None;
+    }
+    return Some((column as i32 - 'a' as i32, row - 1));
+}
+
+/// Writes a wall like `e3h`.
+pub fn wall_to_str(location: Point, orientation: Orientation) -> String {
+    let suffix = match orientation {
+        Orientation::Horizontal => 'h',
+        Orientation::Vertical => 'v',
+    };
+    return format!("{}{}", square_to_str(location), suffix);
+}
+
+/// Reads a wall like `e3h`, in either case.
+pub fn parse_wall(text: &str) -> Option<(Point, Orientation)> {
+    let orientation = match text.chars().last()?.to_ascii_lowercase() {
+        'h' => Orientation::Horizontal,
+        'v' => Orientation::Vertical,
+        _ => return None,
+    };
+    return Some((parse_square(&text[..text.len() - 1])?, orientation));
+}
+
+impl Turn {
+    /// Writes the turn in standard notation. Pawn moves are written as the
+    /// square they land on, so this is `None` if the current player's pawn
+    /// can't make the move.
+    pub fn to_notation(self, game: &Quoridor) -> Option<String> {
+        return match self {
+            Turn::PlaceWall(location, orientation) => Some(wall_to_str(location, orientation)),
+            Turn::Pass => Some(String::from("pass")),
+            _ => game.destination(self).map(square_to_str),
+        };
+    }
+
+    /// Reads a turn in standard notation for the current player.
+    ///
+    /// Walls are only read, not checked, but a pawn move has to name
+    /// a square the pawn can reach, jumps included.
+    pub fn from_notation(text: &str, game: &Quoridor) -> Result<Turn, NotationError> {
+        let text = text.trim();
+        let malformed = || NotationError::Malformed(String::from(text));
+        if text.eq_ignore_ascii_case("pass") {
+            return Ok(Turn::Pass);
+        }
+        if text.ends_with(|c: char| "hvHV".contains(c)) {
+            let (square, orientation) = parse_wall(text).ok_or_else(malformed)?;
+            return Ok(Turn::PlaceWall(square, orientation));
+        }
+
+        let square = parse_square(text).ok_or_else(malformed)?;
+        return game
+            .pawn_turns()
+            .find(|turn| game.destination(*turn) == Some(square))
+            .ok_or(NotationError::Unreachable(square));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::Direction::*;
+
+    #[test]
+    fn squares() {
+        assert_eq!("a1", square_to_str((0, 0)));
+        assert_eq!("e9", square_to_str((4, 8)));
+        assert_eq!("k11", square_to_str((10, 10)));
+        assert_eq!(Some((4, 1)), parse_square("e2"));
+        assert_eq!(Some((4, 1)), parse_square("E2"));
+        assert_eq!(Some((10, 10)), parse_square("k11"));
+        assert_eq!(None, parse_square("e0"));
+        assert_eq!(None, parse_square("e"));
+        assert_eq!(None, parse_square("2e"));
+        assert_eq!(None, parse_square("e+2"));
+        assert_eq!(None, parse_square(""));
+    }
+
+    #[test]
+    fn walls() {
+        let game = Quoridor::new_two_player();
+        let wall = Turn::PlaceWall((4, 2), Orientation::Horizontal);
+        assert_eq!(Some(String::from("e3h")), wall.to_notation(&game));
+        assert_eq!(Ok(wall), Turn::from_notation("e3h", &game));
+        assert_eq!(Ok(wall), Turn::from_notation("E3H", &game));
+
+        let wall = Turn::PlaceWall((0, 7), Orientation::Vertical);
+        assert_eq!(Some(String::from("a8v")), wall.to_notation(&game));
+        assert_eq!(Ok(wall), Turn::from_notation("a8v", &game));
+    }
+
+    #[test]
+    fn pawn_moves() {
+        let game = Quoridor::new_two_player();
+        assert_eq!(Some(String::from("e2")), Turn::MovePawn(Up).to_notation(&game));
+        assert_eq!(Some(String::from("d1")), Turn::MovePawn(Left).to_notation(&game));
+        assert_eq!(None, Turn::MovePawn(Down).to_notation(&game));
+        assert_eq!(Ok(Turn::MovePawn(Up)), Turn::from_notation("e2", &game));
+        assert_eq!(Ok(Turn::MovePawn(Right)), Turn::from_notation("f1", &game));
+        assert_eq!(
+            Err(NotationError::Unreachable((4, 2))),
+            Turn::from_notation("e3", &game)
+        );
+        assert_eq!(
+            Err(NotationError::Malformed(String::from("up"))),
+            Turn::from_notation("up", &game)
+        );
+    }
+
+    #[test]
+    fn jumps() {
+        let mut game = Quoridor::new_two_player();
+        for text in ["e2", "e8", "e3", "e7", "e4", "e6", "e5"].iter() {
+            let turn = Turn::from_notation(text, &game).unwrap();
+            game.play(turn).unwrap();
+        }
+        // Straight over the pawn on e5
+        assert_eq!(Ok(Turn::MovePawn(Down)), Turn::from_notation("e4", &game));
+        assert_eq!(Some(String::from("e4")), Turn::MovePawn(Down).to_notation(&game));
+
+        game.play(Turn::from_notation("e4h", &game).unwrap()).unwrap();
+        game.play(Turn::from_notation("a3h", &game).unwrap()).unwrap();
+        // The wall behind e5 turns the jump sideways
+        assert_eq!(Err(NotationError::Unreachable((4, 3))), Turn::from_notation("e4", &game));
+        assert_eq!(Ok(Turn::JumpPawn(Down, Left)), Turn::from_notation("d5", &game));
+        assert_eq!(Some(String::from("f5")), Turn::JumpPawn(Down, Right).to_notation(&game));
+    }
+}
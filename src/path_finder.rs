@@ -1,113 +1,107 @@
 #![allow(dead_code)]
 use crate::board::{Direction::*, *};
-extern crate priority_queue;
-
-fn shortest_distance(board: Board, from: &Point, to: &Point) -> i32 {
-    return shortest_distance_reccsive(board, from, to);
-}
 
 fn distance_if_no_walls((a_x, a_y): &Point, (b_x, b_y): &Point) -> i32 {
     return (a_x - b_x).abs() + (a_y - b_y).abs();
 }
 
-fn shortest_distance_reccsive(board: Board, from: &Point, to: &Point) -> i32 {
-    use priority_queue::PriorityQueue;
-    let directions = [Up, Right, Left, Down];
-    let mut to_search: PriorityQueue<Point, i32> = PriorityQueue::new();
-    to_search.push(*from, distance_if_no_walls(from, to));
-    let mut min_to_node: HashMap<Point, i32> = HashMap::new();
-    min_to_node.insert(*from, 0);
-
-    while !to_search.is_empty() {
-        let temp = to_search.pop().unwrap();
-        let current_point = &temp.0;
-        let current_distance = temp.1;
-        for direction in directions.iter() {
-            if !board.can_move_from(*current_point, *direction) {
-                continue;
-            }
+pub fn is_path<F>(board: &Board, from: &Point, target: F) -> bool
+where
+    F: Fn(Point) -> bool,
+{
+    if target(*from) {
+        return true;
+    }
 
-            let one_step = shift(&current_point, *direction);
-            let estimate_distance = current_distance + distance_if_no_walls(&one_step, to);
-            if min_to_node.get(&one_step).unwrap_or(&i32::MAX) < &estimate_distance {
+    let mut to_search: Vec<Point> = Vec::from([*from]);
+    let mut searched: HashSet<Point> = HashSet::new();
+    searched.insert(*from);
+    while let Some(current) = to_search.pop() {
+        for direction in [Up, Right, Left, Down].iter() {
+            if !board.can_move_from(current, *direction) {
                 continue;
             }
-            min_to_node.insert(one_step, *min_to_node.get(current_point).unwrap() + 1);
-            //dbg!(estimate_distance);
-            let (x, y) = one_step;
-            let (x1, y1) = *to;
-            if x == x1 && y == y1 {
-                return *min_to_node.get(to).unwrap();
+            let one_step = shift(&current, *direction);
+            if target(one_step) {
+                return true;
             }
-            if one_step == *to {
-                return estimate_distance;
+            if searched.insert(one_step) {
+                to_search.push(one_step);
             }
-
-            to_search.push(one_step, 999 - estimate_distance);
         }
     }
-    return -1;
+    return false;
 }
 
-pub fn is_path<F>(board: Board, from: &Point, target: F) -> bool
-where
-    F: Fn(Point) -> bool,
-{
-    let mut to_search: Vec<Point> = Vec::from([*from]);
-    let mut searched: Vec<Point> = Vec::new();
-    let mut result = false;
-    while !to_search.is_empty() && !result {
-        let current = to_search.pop().unwrap();
+/// The number of steps a pawn needs to reach its goal, ignoring other pawns.
+/// `None` if there is no way through the walls.
+pub fn distance_to_goal(board: &Board, pawn: i8) -> Option<i32> {
+    return path_to_goal(board, pawn).map(|path| path.len() as i32 - 1);
+}
 
-        searched.push(current);
+/// One of the shortest routes from a pawn to its goal, ignoring other pawns.
+/// The route starts with the square the pawn is on.
+pub fn path_to_goal(board: &Board, pawn: i8) -> Option<Vec<Point>> {
+    use std::collections::VecDeque;
+    let from = board.get_pawn(pawn)?;
+    let mut to_search: VecDeque<Point> = VecDeque::from(vec![from]);
+    // Where each square was first reached from
+    let mut came_from: HashMap<Point, Point> = HashMap::new();
+    came_from.insert(from, from);
+    while let Some(current) = to_search.pop_front() {
+        if board.is_goal(pawn, current) {
+            let mut path = vec![current];
+            let mut step = current;
+            while step != from {
+                step = came_from[&step];
+                path.push(step);
+            }
+            path.reverse();
+            return Some(path);
+        }
         for direction in [Up, Right, Left, Down].iter() {
+            if !board.can_move_from(current, *direction) {
+                continue;
+            }
             let one_step = shift(&current, *direction);
-            if board.can_move_from(current, *direction) {
-                if !searched.contains(&one_step) {
-                    to_search.insert(0, one_step);
-                }
-                if target(one_step) {
-                    result = true;
-                    break;
-                }
+            if came_from.contains_key(&one_step) {
+                continue;
             }
+            came_from.insert(one_step, current);
+            to_search.push_back(one_step);
         }
     }
-    return result;
-}
-
-use std::collections::HashMap;
-fn print_shortest_path(hash: HashMap<Point, i32>, width: i32, height: i32) {
-    for not_y in 0..height {
-        for x in 0..width {
-            let y = 9 - not_y;
-            let value = hash.get(&(x, y)).unwrap_or(&-1);
-            print!("|{:02}|", value);
-        }
-        println!("");
-    }
+    return None;
 }
 
+use std::collections::{HashMap, HashSet};
 #[cfg(test)]
 mod test {
     use crate::board::Board;
     use crate::path_finder::*;
+    /// Steps from one square to another, by adding a pawn going there.
+    fn distance_between(board: Board, from: Point, to: Point) -> Option<i32> {
+        use crate::goal::Goal;
+        let pawn = board.get_players();
+        return distance_to_goal(&board.add_player(from, Goal::Squares(vec![to])), pawn);
+    }
+
     #[test]
     fn no_walls_0() {
         let board = Board::create_default();
-        assert_eq!(11, shortest_distance(board, &(4, 7), &(0, 0)));
+        assert_eq!(Some(11), distance_between(board, (4, 7), (0, 0)));
     }
 
     #[test]
     fn no_walls_1() {
         let board = Board::create_default();
-        assert_eq!(4, shortest_distance(board, &(3, 4), &(7, 4)));
+        assert_eq!(Some(4), distance_between(board, (3, 4), (7, 4)));
     }
 
     #[test]
     fn no_walls_2() {
         let board = Board::create_default();
-        assert_eq!(16, shortest_distance(board, &(0, 0), &(8, 8)));
+        assert_eq!(Some(16), distance_between(board, (0, 0), (8, 8)));
     }
 
     #[test]
@@ -115,19 +109,19 @@ mod test {
         let board = Board::create_default()
             .place_wall((4, 4), Orientation::Horizontal)
             .place_wall((3, 3), Orientation::Vertical);
-        assert_eq!(10, shortest_distance(board, &(4, 0), &(4, 8)))
+        assert_eq!(Some(10), distance_between(board, (4, 0), (4, 8)));
     }
 
     #[test]
     fn is_path_no_walls() {
         let board = Board::create_default();
-        assert!(is_path(board, &(0, 0), |(_, y)| return y == 8));
+        assert!(is_path(&board, &(0, 0), |(_, y)| return y == 8));
     }
 
     #[test]
     fn is_path_no_walls_2() {
         let board = Board::create_default();
-        assert!(is_path(board, &(7, 7), |(_, y)| y == 0));
+        assert!(is_path(&board, &(7, 7), |(_, y)| y == 0));
     }
 
     #[test]
@@ -139,7 +133,60 @@ mod test {
             .place_wall((6, 2), Orientation::Horizontal)
             .place_wall((7, 2), Orientation::Vertical)
             .place_wall((7, 1), Orientation::Horizontal);
-        println!("\n\n\n\n\n\n\n No really \n\n\n\n\n");
-        assert!(!is_path(board, &(0, 0), |(_, y)| y == 7));
+        assert!(!is_path(&board, &(0, 0), |(_, y)| y == 7));
+    }
+
+    #[test]
+    fn distance_to_goal_no_walls() {
+        let board = Board::create_default();
+        assert_eq!(Some(8), distance_to_goal(&board, 0));
+        assert_eq!(Some(8), distance_to_goal(&board, 1));
+        assert_eq!(None, distance_to_goal(&board, 2));
+    }
+
+    #[test]
+    fn distance_to_goal_reads_goal() {
+        use crate::goal::Goal;
+        let board = Board::create()
+            .set_width(5)
+            .set_height(5)
+            .add_player((0, 0), Goal::Squares(vec![(4, 4)]));
+        assert_eq!(Some(8), distance_to_goal(&board, 0));
+
+        let board = Board::create_four_player();
+        assert_eq!(Some(8), distance_to_goal(&board, 1));
+        assert_eq!(Some(8), distance_to_goal(&board, 3));
+    }
+
+    #[test]
+    fn distance_to_goal_around_walls() {
+        let board = Board::create_default()
+            .place_wall((3, 0), Orientation::Horizontal)
+            .place_wall((5, 0), Orientation::Horizontal);
+        assert_eq!(Some(10), distance_to_goal(&board, 0));
+    }
+
+    #[test]
+    fn path_to_goal_steps() {
+        let board = Board::create_default().place_wall((3, 0), Orientation::Horizontal);
+        let path = path_to_goal(&board, 0).unwrap();
+        assert_eq!(10, path.len());
+        assert_eq!((4, 0), path[0]);
+        assert_eq!(8, path.last().unwrap().1);
+        for step in path.windows(2) {
+            assert_eq!(1, distance_if_no_walls(&step[0], &step[1]));
+        }
+    }
+
+    #[test]
+    fn no_distance_when_blocked() {
+        let board = Board::create_default()
+            .place_wall((0, 2), Orientation::Horizontal)
+            .place_wall((2, 2), Orientation::Horizontal)
+            .place_wall((4, 2), Orientation::Horizontal)
+            .place_wall((6, 2), Orientation::Horizontal)
+            .place_wall((7, 2), Orientation::Vertical)
+            .place_wall((7, 1), Orientation::Horizontal);
+        assert_eq!(None, distance_to_goal(&board, 0));
     }
 }
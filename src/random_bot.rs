@@ -0,0 +1,77 @@
+#![allow(dead_code)]
+
+//! A computer player that plays any legal turn, for smoke tests and as the
+//! weakest opponent.
+
+use crate::agent::Agent;
+use crate::game::{Quoridor, Turn};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Picks each turn uniformly from every legal pawn move and wall.
+///
+/// The same seed always plays the same game against the same opponent.
+pub struct RandomBot {
+    rng: ChaCha8Rng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        return RandomBot {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+    }
+}
+
+impl Agent for RandomBot {
+    fn choose_turn(&mut self, game: &Quoridor) -> Turn {
+        let count = game.legal_turn_count();
+        if count == 0 {
+            panic!("There are no legal turns to choose from");
+        }
+        let choice = self.rng.gen_range(0..count);
+        return game.legal_turns().nth(choice).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::game::GameStatus;
+    use crate::rules::RuleSet;
+
+    fn random_game(rules: RuleSet, seed: u64) -> Quoridor {
+        let mut game = Quoridor::new(rules.set_turn_limit(Some(400)));
+        let mut agents: Vec<Box<dyn Agent>> = (0..game.number_of_players())
+            .map(|player| Box::new(RandomBot::new(seed + player as u64)) as Box<dyn Agent>)
+            .collect();
+        play_game(&mut game, &mut agents, |_| ());
+        return game;
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let first = random_game(RuleSet::standard_two_player(), 7);
+        let second = random_game(RuleSet::standard_two_player(), 7);
+        assert_eq!(first.history(), second.history());
+        let other = random_game(RuleSet::standard_two_player(), 8);
+        assert_ne!(first.history(), other.history());
+    }
+
+    #[test]
+    fn random_games_finish() {
+        for seed in 0..4 {
+            let game = random_game(RuleSet::standard_two_player(), seed);
+            assert_ne!(GameStatus::InProgress, game.status());
+        }
+        let game = random_game(RuleSet::standard_four_player(), 0);
+        assert_ne!(GameStatus::InProgress, game.status());
+    }
+
+    #[test]
+    fn plays_walls_too() {
+        let game = random_game(RuleSet::standard_two_player(), 1);
+        assert!(game.history().iter().any(|turn| matches!(turn, Turn::PlaceWall(..))));
+    }
+}
@@ -0,0 +1,802 @@
+#![allow(dead_code)]
+
+//! Game records, in a format modelled on chess PGN.
+//!
+//! A record is a list of tags followed by the turns in standard notation
+//! with move numbers, comments in braces and variations in brackets, ending
+//! with the result. A file can hold any number of records one after another.
+//!
+//! ```text
+//! [Variant "Standard"]
+//! [Player1 "Alice"]
+//! [Player2 "Bob"]
+//! [Date "2021.05.02"]
+//! [Result "1-0"]
+//! [Termination "Resignation"]
+//!
+//! 1. e2 e8 2. e3 {Heading straight up} e7 (2... e3h 3. d3) 3. e4 1-0
+//! ```
+//!
+//! The `Variant` tag is `Standard` for two players or `Four player`, with
+//! `Players`, `Board`, `Walls`, `Start` and `TurnLimit` tags when the rules
+//! differ from those. `Start` lists each player's starting square: `a5,i5`.
+//! Games that didn't begin at the start of their rules have a `Position`
+//! tag with the position string they began from.
+//! Results give each player's score in turn: `1-0`, `0-1`, `1/2-1/2` or
+//! `1-0-0-0` for four players, and `*` for a game still being played.
+//! Games saved part way through can also have an `Undone` tag with turns
+//! that can be redone and a `DrawOffers` tag with players offering a draw.
+
+use crate::game::{GameStatus, Quoridor, Turn};
+use crate::notation::{parse_square, square_to_str};
+use crate::rules::RuleSet;
+use crate::save::LoadError;
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+/// A turn in a record along with anything said about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordMove {
+    pub turn: Turn,
+    pub comment: Option<String>,
+    /// Lines that could have been played instead of this turn
+    pub variations: Vec<Vec<RecordMove>>,
+}
+
+impl RecordMove {
+    pub fn new(turn: Turn) -> RecordMove {
+        return RecordMove {
+            turn,
+            comment: None,
+            variations: Vec::new(),
+        };
+    }
+}
+
+/// One game with its tags, comments and variations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    /// In the order they are written
+    tags: Vec<(String, String)>,
+    /// Said about the game before the first turn
+    comment: Option<String>,
+    moves: Vec<RecordMove>,
+}
+
+impl GameRecord {
+    /// A record of the game so far, with the variant, result and
+    /// termination filled in and the players left unknown.
+    pub fn from_game(game: &Quoridor) -> GameRecord {
+        let mut record = GameRecord {
+            tags: Vec::new(),
+            comment: None,
+            moves: Vec::new(),
+        };
+        let rules = game.rules();
+        let players = rules.get_players();
+        let standard = match players {
+            4 => RuleSet::standard_four_player(),
+            _ => RuleSet::standard_two_player().set_players(players),
+        };
+        record = record.set_tag("Variant", if players == 4 { "Four player" } else { "Standard" });
+        if players != 2 && players != 4 {
+            record = record.set_tag("Players", &players.to_string());
+        }
+        let size = (rules.get_width(), rules.get_height());
+        if size != (standard.get_width(), standard.get_height()) {
+            let board = format!("{}x{}", rules.get_width(), rules.get_height());
+            record = record.set_tag("Board", &board);
+        }
+        if rules.get_walls_per_player() != standard.get_walls_per_player() {
+            record = record.set_tag("Walls", &rules.get_walls_per_player().to_string());
+        }
+        let starting_squares = rules.starting_squares();
+        let standard = standard.set_width(rules.get_width()).set_height(rules.get_height());
+        if starting_squares != standard.starting_squares() {
+            let squares: Vec<String> = starting_squares.into_iter().map(square_to_str).collect();
+            record = record.set_tag("Start", &squares.join(","));
+        }
+        if let Some(limit) = rules.get_turn_limit() {
+            record = record.set_tag("TurnLimit", &limit.to_string());
+        }
+        let start = game.rewound().to_position_string();
+        if start != Quoridor::new(rules.clone()).to_position_string() {
+            record = record.set_tag("Position", &start);
+        }
+        for player in 1..=players {
+            record = record.set_tag(&format!("Player{}", player), "?");
+        }
+        record = record
+            .set_tag("Date", "????.??.??")
+            .set_tag("Result", &result_to_str(game));
+        let termination = match game.status() {
+            GameStatus::InProgress => None,
+            GameStatus::Won { .. } => Some("Goal"),
+            GameStatus::Resigned { .. } => Some("Resignation"),
+            GameStatus::DrawAgreed => Some("Agreement"),
+            GameStatus::DrawByRule => Some("Turn limit"),
+        };
+        if let Some(termination) = termination {
+            record = record.set_tag("Termination", termination);
+        }
+        if !game.draw_offers().is_empty() && !game.is_over() {
+            let offers: Vec<String> = game
+                .draw_offers()
+                .iter()
+                .map(|player| (player + 1).to_string())
+                .collect();
+            record = record.set_tag("DrawOffers", &offers.join(","));
+        }
+        if !game.undone().is_empty() {
+            record = record.set_tag("Undone", &game.undone_to_notation().join(" "));
+        }
+        record.moves = game.history().iter().map(|turn| RecordMove::new(*turn)).collect();
+        return record;
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        return self
+            .tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    /// Sets a tag, keeping its place if it is already there.
+    pub fn set_tag(mut self, name: &str, value: &str) -> GameRecord {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = String::from(value),
+            None => self.tags.push((String::from(name), String::from(value))),
+        }
+        return self;
+    }
+
+    pub fn remove_tag(mut self, name: &str) -> GameRecord {
+        self.tags.retain(|(tag, _)| tag != name);
+        return self;
+    }
+
+    pub fn get_tags(&self) -> &[(String, String)] {
+        return &self.tags;
+    }
+
+    pub fn set_comment(mut self, comment: Option<String>) -> GameRecord {
+        self.comment = comment;
+        return self;
+    }
+
+    pub fn get_comment(&self) -> Option<&str> {
+        return self.comment.as_deref();
+    }
+
+    pub fn set_moves(mut self, moves: Vec<RecordMove>) -> GameRecord {
+        self.moves = moves;
+        return self;
+    }
+
+    /// The main line of the game.
+    pub fn get_moves(&self) -> &[RecordMove] {
+        return &self.moves;
+    }
+
+    /// The rules given by the `Variant`, `Players`, `Board`, `Walls`, `Start`
+    /// and `TurnLimit` tags.
+    pub fn rules(&self) -> Result<RuleSet, String> {
+        let mut rules = match self.get_tag("Variant").unwrap_or("Standard") {
+            "Standard" => RuleSet::standard_two_player(),
+            "Four player" => RuleSet::standard_four_player(),
+            variant => return Err(format!("unknown variant \"{}\"", variant)),
+        };
+        if let Some(players) = self.get_tag("Players") {
+            let players = players
+                .parse()
+                .map_err(|_| format!("\"{}\" is not a number of players", players))?;
+            rules = rules.set_players(players);
+        }
+        if let Some(board) = self.get_tag("Board") {
+            let (width, height) = board
+                .split_once('x')
+                .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                .ok_or(format!("\"{}\" is not a board size", board))?;
+            rules = rules.set_width(width).set_height(height);
+        }
+        if let Some(walls) = self.get_tag("Walls") {
+            let walls = walls
+                .parse()
+                .map_err(|_| format!("\"{}\" is not a number of walls", walls))?;
+            rules = rules.set_walls_per_player(walls);
+        }
+        if let Some(start) = self.get_tag("Start") {
+            let squares = start
+                .split(',')
+                .map(|square| parse_square(square).ok_or(format!("\"{}\" is not a square", square)))
+                .collect::<Result<Vec<_>, _>>()?;
+            rules = rules.set_starting_squares(squares);
+        }
+        if let Some(limit) = self.get_tag("TurnLimit") {
+            let limit = limit
+                .parse()
+                .map_err(|_| format!("\"{}\" is not a turn limit", limit))?;
+            rules = rules.set_turn_limit(Some(limit));
+        }
+        rules.validate()?;
+        return Ok(rules);
+    }
+
+    /// The game before the first turn, set up from the `Position` tag if
+    /// there is one, otherwise the start of the rules.
+    pub fn start(&self) -> Result<Quoridor, String> {
+        let rules = self.rules()?;
+        return match self.get_tag("Position") {
+            Some(position) => Quoridor::from_position_string(position)?.with_rules(rules),
+            None => Ok(Quoridor::new(rules)),
+        };
+    }
+
+    /// Plays the main line from the start, then ends the game by resignation
+    /// or agreement if the `Termination` tag says so.
+    ///
+    /// Turns in the `Undone` tag are played and taken back again so they
+    /// can be redone, and players in the `DrawOffers` tag offer a draw.
+    pub fn to_game(&self) -> Result<Quoridor, String> {
+        let mut game = self.start()?;
+        for (i, record_move) in self.moves.iter().enumerate() {
+            game.play(record_move.turn)
+                .map_err(|reason| format!("turn {} is illegal: {}", i + 1, reason))?;
+        }
+        if let Some(undone) = self.get_tag("Undone") {
+            let undone: Vec<&str> = undone.split_whitespace().collect();
+            for text in undone.iter() {
+                let turn = Turn::from_notation(text, &game).map_err(|reason| reason.to_string())?;
+                game.play(turn)
+                    .map_err(|reason| format!("undone turn {} is illegal: {}", text, reason))?;
+            }
+            for _ in undone.iter() {
+                game.undo();
+            }
+        }
+        if let Some(offers) = self.get_tag("DrawOffers") {
+            for offer in offers.split(',') {
+                let player: i32 = offer
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a player", offer))?;
+                if !(1..=game.number_of_players()).contains(&player) {
+                    return Err(format!("there is no player {} to offer a draw", player));
+                }
+                game.offer_draw(player - 1).map_err(|reason| reason.to_string())?;
+            }
+        }
+
+        self.end_game(&mut game)?;
+        return Ok(game);
+    }
+
+    /// Ends the game by resignation or agreement if the `Termination`
+    /// tag says so, for the game at the end of the main line.
+    pub fn end_game(&self, game: &mut Quoridor) -> Result<(), String> {
+        let scores: Vec<&str> = self.get_tag("Result").unwrap_or("*").split('-').collect();
+        match self.get_tag("Termination") {
+            Some("Resignation") => {
+                let player = scores
+                    .iter()
+                    .position(|score| *score == "0")
+                    .filter(|_| scores.len() as i32 == game.number_of_players())
+                    .ok_or("can't tell who resigned from the result")?;
+                game.resign(player as i32).map_err(|reason| reason.to_string())?;
+            }
+            Some("Agreement") => {
+                for player in 0..game.number_of_players() {
+                    game.offer_draw(player).map_err(|reason| reason.to_string())?;
+                }
+            }
+            _ => (),
+        }
+        return Ok(());
+    }
+}
+
+/// Each player's score with dashes between, or `*` if the game isn't over.
+pub fn result_to_str(game: &Quoridor) -> String {
+    let players = game.number_of_players();
+    let scores: Vec<String> = (0..players)
+        .map(|player| match game.status() {
+            GameStatus::InProgress => String::new(),
+            GameStatus::Won { player: winner } if winner == player => String::from("1"),
+            GameStatus::Won { .. } => String::from("0"),
+            GameStatus::Resigned { player: loser } if loser == player => String::from("0"),
+            GameStatus::Resigned { .. } if players == 2 => String::from("1"),
+            GameStatus::Resigned { .. } => format!("1/{}", players - 1),
+            GameStatus::DrawAgreed | GameStatus::DrawByRule => format!("1/{}", players),
+        })
+        .collect();
+    if !game.is_over() {
+        return String::from("*");
+    }
+    return scores.join("-");
+}
+
+fn is_result(word: &str) -> bool {
+    return word == "*"
+        || (word.contains('-') && word.chars().all(|c| c.is_ascii_digit() || "/-".contains(c)));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Open,
+    Close,
+    Move(String),
+    Result(String),
+}
+
+/// Splits records into tokens, each with the line it starts on.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, LoadError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        let start = line;
+        let error = |reason: &str| LoadError {
+            line: start,
+            reason: String::from(reason),
+        };
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => (),
+            '(' => tokens.push((start, Token::Open)),
+            ')' => tokens.push((start, Token::Close)),
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            comment.push(c);
+                        }
+                        None => return Err(error("comment is never closed")),
+                    }
+                }
+                let comment: Vec<&str> = comment.split_whitespace().collect();
+                tokens.push((start, Token::Comment(comment.join(" "))));
+            }
+            ';' => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '[' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                while chars.next_if(|c| *c == ' ').is_some() {}
+                if name.is_empty() || chars.next() != Some('"') {
+                    return Err(error("tags look like [Name \"value\"]"));
+                }
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if c != '\n' => value.push(c),
+                            _ => return Err(error("tag value is never closed")),
+                        },
+                        Some(c) if c != '\n' => value.push(c),
+                        _ => return Err(error("tag value is never closed")),
+                    }
+                }
+                while chars.next_if(|c| *c == ' ').is_some() {}
+                if chars.next() != Some(']') {
+                    return Err(error("tags look like [Name \"value\"]"));
+                }
+                tokens.push((start, Token::Tag(name, value)));
+            }
+            c => {
+                let mut word = String::from(c);
+                let in_word = |c: &char| !c.is_whitespace() && !"[]{}();".contains(*c);
+                while let Some(c) = chars.next_if(in_word) {
+                    word.push(c);
+                }
+                if is_result(&word) {
+                    tokens.push((start, Token::Result(word)));
+                    continue;
+                }
+                // Move numbers like `12.` or `12...`, maybe run into the move
+                let turn = word.trim_start_matches(|c: char| c.is_ascii_digit());
+                let turn = if turn.len() < word.len() && turn.starts_with('.') {
+                    turn.trim_start_matches('.')
+                } else {
+                    &word
+                };
+                if !turn.is_empty() {
+                    tokens.push((start, Token::Move(String::from(turn))));
+                }
+            }
+        }
+    }
+    return Ok(tokens);
+}
+
+/// Reads turns until the end of the line, checking each one is legal.
+/// Variations are read with the game as it was before the turn they replace.
+fn read_moves(
+    tokens: &mut Peekable<IntoIter<(usize, Token)>>,
+    mut game: Quoridor,
+    nested: bool,
+    comment: &mut Option<String>,
+) -> Result<Vec<RecordMove>, LoadError> {
+    let mut moves: Vec<RecordMove> = Vec::new();
+    let mut before: Option<Quoridor> = None;
+    let mut last_line = 0;
+    loop {
+        let (line, token) = match tokens.peek() {
+            Some((line, token)) => (*line, token.clone()),
+            None if nested => {
+                return Err(LoadError {
+                    line: last_line,
+                    reason: String::from("variation is never closed"),
+                })
+            }
+            None => return Ok(moves),
+        };
+        last_line = line;
+        let error = |reason: String| LoadError { line, reason };
+        match token {
+            Token::Tag(..) | Token::Result(_) if nested => {
+                return Err(error(String::from("variation is never closed")));
+            }
+            Token::Tag(..) | Token::Result(_) => return Ok(moves),
+            Token::Close if nested => {
+                tokens.next();
+                return Ok(moves);
+            }
+            Token::Close => return Err(error(String::from("there is no variation to close"))),
+            Token::Open => {
+                tokens.next();
+                let start = before
+                    .clone()
+                    .ok_or_else(|| error(String::from("a variation has to follow a turn")))?;
+                let variation = read_moves(tokens, start, true, &mut None)?;
+                if variation.is_empty() {
+                    return Err(error(String::from("variation has no turns")));
+                }
+                moves.last_mut().unwrap().variations.push(variation);
+            }
+            Token::Comment(text) => {
+                tokens.next();
+                let target = match moves.last_mut() {
+                    Some(last) => &mut last.comment,
+                    None if !nested => &mut *comment,
+                    None => return Err(error(String::from("a comment has to follow a turn"))),
+                };
+                *target = Some(match target.take() {
+                    Some(earlier) => format!("{} {}", earlier, text),
+                    None => text,
+                });
+            }
+            Token::Move(text) => {
+                tokens.next();
+                let turn = Turn::from_notation(&text, &game)
+                    .map_err(|reason| error(reason.to_string()))?;
+                before = Some(game.clone());
+                game.play(turn)
+                    .map_err(|reason| error(format!("{} is illegal, {}", text, reason)))?;
+                moves.push(RecordMove::new(turn));
+            }
+        }
+    }
+}
+
+/// Reads every record in the text, checking the turns are legal.
+pub fn read_records(text: &str) -> Result<Vec<GameRecord>, LoadError> {
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    let mut records = Vec::new();
+    while tokens.peek().is_some() {
+        let mut record = GameRecord {
+            tags: Vec::new(),
+            comment: None,
+            moves: Vec::new(),
+        };
+        let mut line = 0;
+        while let Some((tag_line, Token::Tag(..))) = tokens.peek() {
+            line = *tag_line;
+            if let Some((_, Token::Tag(name, value))) = tokens.next() {
+                record = record.set_tag(&name, &value);
+            }
+        }
+        if let Some((move_line, _)) = tokens.peek() {
+            line = *move_line;
+        }
+
+        let start = record.start().map_err(|reason| LoadError { line, reason })?;
+        let mut comment = None;
+        record.moves = read_moves(&mut tokens, start, false, &mut comment)?;
+        record.comment = comment;
+        if let Some((_, Token::Result(result))) = tokens.peek() {
+            if record.get_tag("Result").is_none() {
+                record = record.set_tag("Result", result);
+            }
+            tokens.next();
+        }
+        records.push(record);
+    }
+    return Ok(records);
+}
+
+/// Writes a line of turns with move numbers, `N.` at the start of each
+/// round and `N...` when picking up part way through one.
+fn write_moves(moves: &[RecordMove], mut game: Quoridor, words: &mut Vec<String>) {
+    let mut needs_number = true;
+    for record_move in moves {
+        // Games set up from a position can start with any player to move
+        let players = game.number_of_players();
+        let played = game.history().len() as i32;
+        let ply = played + (game.current_player() - played).rem_euclid(players);
+        if ply % players == 0 {
+            words.push(format!("{}.", ply / players + 1));
+        } else if needs_number {
+            words.push(format!("{}...", ply / players + 1));
+        }
+        needs_number = false;
+        words.push(record_move.turn.to_notation(&game).unwrap_or_else(|| String::from("??")));
+        if let Some(comment) = &record_move.comment {
+            words.push(format!("{{{}}}", comment.replace('}', ")")));
+            needs_number = true;
+        }
+        for variation in record_move.variations.iter() {
+            words.push(String::from("("));
+            write_moves(variation, game.clone(), words);
+            words.push(String::from(")"));
+            needs_number = true;
+        }
+        if game.play(record_move.turn).is_err() {
+            // Anything after an illegal turn can't be written in notation
+            break;
+        }
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.tags.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut words = Vec::new();
+        if let Some(comment) = &self.comment {
+            words.push(format!("{{{}}}", comment.replace('}', ")")));
+        }
+        if let Ok(start) = self.start() {
+            write_moves(&self.moves, start, &mut words);
+        }
+        words.push(String::from(self.get_tag("Result").unwrap_or("*")));
+
+        // Keep lines under 80 characters, with brackets hugging what they hold
+        let mut line = String::new();
+        let mut after_open = false;
+        for word in words.iter().flat_map(|word| word.split(' ')) {
+            if word == ")" {
+                line.push(')');
+                continue;
+            }
+            if !line.is_empty() && !after_open {
+                if line.len() + word.len() >= 80 {
+                    writeln!(f, "{}", line)?;
+                    line.clear();
+                } else {
+                    line.push(' ');
+                }
+            }
+            line.push_str(word);
+            after_open = word == "(";
+        }
+        return writeln!(f, "{}", line);
+    }
+}
+
+/// Writes records one after another, with a blank line between them.
+pub fn write_records(records: &[GameRecord]) -> String {
+    let records: Vec<String> = records.iter().map(|record| record.to_string()).collect();
+    return records.join("\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::Direction::*;
+    use crate::board::Orientation;
+
+    const RECORDS: &str = r#"[Variant "Standard"]
+[Player1 "Alice \"Ace\""]
+[Player2 "Bob"]
+[Date "2021.05.02"]
+[Result "1-0"]
+[Termination "Resignation"]
+
+{A short game} 1. e2 e8 2. e3 {Heading straight up} e7 (2... e3h 3. d3 (3. f3)
+e7) 3. e4 1-0
+
+[Variant "Four player"]
+[Result "*"]
+
+1. e2 b5 e8 h5 2. e3 *
+"#;
+
+    #[test]
+    fn read_many_records() {
+        let records = read_records(RECORDS).unwrap();
+        assert_eq!(2, records.len());
+
+        let first = &records[0];
+        assert_eq!(Some("Alice \"Ace\""), first.get_tag("Player1"));
+        assert_eq!(Some("A short game"), first.get_comment());
+        let moves = first.get_moves();
+        assert_eq!(5, moves.len());
+        assert_eq!(Turn::MovePawn(Up), moves[2].turn);
+        assert_eq!(Some("Heading straight up"), moves[2].comment.as_deref());
+        let variation = &moves[3].variations[0];
+        assert_eq!(Turn::PlaceWall((4, 2), Orientation::Horizontal), variation[0].turn);
+        assert_eq!(Turn::MovePawn(Left), variation[1].turn);
+        assert_eq!(Turn::MovePawn(Right), variation[1].variations[0][0].turn);
+
+        let game = first.to_game().unwrap();
+        assert_eq!(GameStatus::Resigned { player: 1 }, game.status());
+        assert_eq!((4, 3), game.board().get_pawn(0).unwrap());
+
+        let second = &records[1];
+        assert_eq!(4, second.rules().unwrap().get_players());
+        assert_eq!(5, second.get_moves().len());
+        assert!(!second.to_game().unwrap().is_over());
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let records = read_records(RECORDS).unwrap();
+        let written = write_records(&records);
+        assert_eq!(records, read_records(&written).unwrap());
+        let words: Vec<&str> = written.split_whitespace().collect();
+        assert!(words
+            .join(" ")
+            .contains("2... e7 (2... e3h 3. d3 (3. f3) 3... e7) 3. e4 1-0"));
+        assert!(written.contains("[Player1 \"Alice \\\"Ace\\\"\"]"));
+    }
+
+    #[test]
+    fn record_from_game() {
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        let record = GameRecord::from_game(&game).set_tag("Player1", "Alice");
+        assert_eq!(Some("*"), record.get_tag("Result"));
+        assert_eq!(None, record.get_tag("Termination"));
+        assert_eq!(
+            "[Variant \"Standard\"]\n[Player1 \"Alice\"]\n[Player2 \"?\"]\n\
+             [Date \"????.??.??\"]\n[Result \"*\"]\n\n1. e2 e3h *\n",
+            record.to_string()
+        );
+
+        game.offer_draw(0).unwrap();
+        game.offer_draw(1).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(Some("1/2-1/2"), record.get_tag("Result"));
+        let loaded = read_records(&record.to_string()).unwrap().remove(0);
+        assert_eq!(GameStatus::DrawAgreed, loaded.to_game().unwrap().status());
+    }
+
+    #[test]
+    fn record_keeps_rules() {
+        let rules = RuleSet::standard_four_player()
+            .set_width(7)
+            .set_height(7)
+            .set_walls_per_player(3)
+            .set_turn_limit(Some(50));
+        let mut game = Quoridor::new(rules.clone());
+        game.resign(2).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(Some("1/3-1/3-0-1/3"), record.get_tag("Result"));
+        assert_eq!(Ok(rules), record.rules());
+        let loaded = read_records(&record.to_string()).unwrap().remove(0);
+        assert_eq!(GameStatus::Resigned { player: 2 }, loaded.to_game().unwrap().status());
+    }
+
+    #[test]
+    fn record_keeps_three_player_rules() {
+        let rules = RuleSet::standard_two_player()
+            .set_players(3)
+            .set_walls_per_player(7)
+            .set_starting_squares(vec![(0, 3), (8, 4), (4, 8)]);
+        let mut game = Quoridor::new(rules.clone());
+        game.play(Turn::MovePawn(Right)).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(Some("3"), record.get_tag("Players"));
+        assert_eq!(Some("a4,i5,e9"), record.get_tag("Start"));
+        assert_eq!(Ok(rules), record.rules());
+        let loaded = read_records(&record.to_string()).unwrap().remove(0).to_game().unwrap();
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+
+        let error = record.clone().set_tag("Start", "a4,i5").rules().unwrap_err();
+        assert!(error.contains("2 starting squares for 3 players"), "{}", error);
+        assert!(record.set_tag("Players", "three").rules().is_err());
+    }
+
+    #[test]
+    fn record_keeps_starting_position() {
+        let start = "9x9 e5,e6 e3h 9,10 2";
+        let rules = RuleSet::standard_two_player().set_turn_limit(Some(40));
+        let mut game = Quoridor::from_position_string(start).unwrap().with_rules(rules).unwrap();
+        game.play(Turn::PlaceWall((3, 5), Orientation::Horizontal)).unwrap();
+        game.play(Turn::MovePawn(Left)).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(Some(start), record.get_tag("Position"));
+        assert_eq!(Some("40"), record.get_tag("TurnLimit"));
+        assert!(record.to_string().ends_with("\n1... d6h 2. d5 *\n"), "{}", record);
+
+        let loaded = read_records(&record.to_string()).unwrap().remove(0).to_game().unwrap();
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+        assert_eq!(game.history(), loaded.history());
+        assert_eq!(game.rules(), loaded.rules());
+        assert_eq!(start, loaded.rewound().to_position_string());
+
+        // Games from the start of their rules don't need the tag
+        assert_eq!(None, GameRecord::from_game(&Quoridor::new_two_player()).get_tag("Position"));
+        // The position has to fit the rules
+        let error = record.set_tag("Walls", "5").to_game().unwrap_err();
+        assert!(error.contains("walls left"), "{}", error);
+    }
+
+    #[test]
+    fn record_keeps_undone_turns_and_draw_offers() {
+        let mut game = Quoridor::new_two_player();
+        for turn in [Turn::MovePawn(Up), Turn::MovePawn(Down), Turn::MovePawn(Up)].iter() {
+            game.play(*turn).unwrap();
+        }
+        game.undo();
+        game.undo();
+        game.offer_draw(1).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(Some("e8 e3"), record.get_tag("Undone"));
+        assert_eq!(Some("2"), record.get_tag("DrawOffers"));
+
+        let mut loaded = read_records(&record.to_string()).unwrap().remove(0).to_game().unwrap();
+        assert_eq!(game.history(), loaded.history());
+        assert_eq!(game.undone(), loaded.undone());
+        assert_eq!(game.draw_offers(), loaded.draw_offers());
+        assert_eq!(Some(Turn::MovePawn(Down)), loaded.redo());
+        assert_eq!(Some(Turn::MovePawn(Up)), loaded.redo());
+    }
+
+    #[test]
+    fn long_games_wrap() {
+        let mut game = Quoridor::new_two_player();
+        for _ in 0..20 {
+            let turn = game.legal_turns().last().unwrap();
+            game.play(turn).unwrap();
+        }
+        let written = GameRecord::from_game(&game).to_string();
+        assert!(written.lines().all(|line| line.len() < 80));
+        let loaded = read_records(&written).unwrap().remove(0);
+        assert_eq!(game.history(), loaded.to_game().unwrap().history());
+    }
+
+    #[test]
+    fn errors_give_line_numbers() {
+        let error = read_records("[Variant \"Standard\"]\n\n1. e2 e8\n2. e4").unwrap_err();
+        assert_eq!(4, error.line);
+        let error = read_records("1. e2 e8\n2. e3 (e4h").unwrap_err();
+        assert_eq!(2, error.line);
+        let error = read_records("1. e2 {never closed\n").unwrap_err();
+        assert_eq!(1, error.line);
+        let error = read_records("[Variant \"Chess\"]\n1. e4").unwrap_err();
+        assert_eq!(2, error.line);
+        let error = read_records("1. e2 )").unwrap_err();
+        assert_eq!(1, error.line);
+        let error = read_records("[Variant Standard]").unwrap_err();
+        assert_eq!(1, error.line);
+    }
+}
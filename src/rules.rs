@@ -0,0 +1,242 @@
+#![allow(dead_code)]
+
+use crate::board::{Board, Point};
+use crate::goal::{Edge, Goal};
+
+/// Everything needed to set up a game: the size of the board,
+/// how many players there are, where they start and how many walls they get.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    width: i32,
+    height: i32,
+    players: i32,
+    walls_per_player: i32,
+    /// Empty means the middle of the edges, see `starting_squares`
+    starting_squares: Vec<Point>,
+    /// The game is drawn once this many turns have been played without a winner
+    turn_limit: Option<i32>,
+}
+
+impl RuleSet {
+    /// The standard 9x9 game with 10 walls each
+    pub fn standard_two_player() -> RuleSet {
+        return RuleSet {
+            width: 9,
+            height: 9,
+            players: 2,
+            walls_per_player: 10,
+            starting_squares: Vec::new(),
+            turn_limit: None,
+        };
+    }
+
+    /// The standard 9x9 game with 5 walls each
+    pub fn standard_four_player() -> RuleSet {
+        return RuleSet::standard_two_player()
+            .set_players(4)
+            .set_walls_per_player(5);
+    }
+
+    pub fn set_width(mut self, width: i32) -> RuleSet {
+        self.width = width;
+        return self;
+    }
+
+    pub fn get_width(&self) -> i32 {
+        return self.width;
+    }
+
+    pub fn set_height(mut self, height: i32) -> RuleSet {
+        self.height = height;
+        return self;
+    }
+
+    pub fn get_height(&self) -> i32 {
+        return self.height;
+    }
+
+    pub fn set_players(mut self, players: i32) -> RuleSet {
+        self.players = players;
+        return self;
+    }
+
+    pub fn get_players(&self) -> i32 {
+        return self.players;
+    }
+
+    pub fn set_walls_per_player(mut self, walls: i32) -> RuleSet {
+        self.walls_per_player = walls;
+        return self;
+    }
+
+    pub fn get_walls_per_player(&self) -> i32 {
+        return self.walls_per_player;
+    }
+
+    pub fn set_turn_limit(mut self, turn_limit: Option<i32>) -> RuleSet {
+        self.turn_limit = turn_limit;
+        return self;
+    }
+
+    pub fn get_turn_limit(&self) -> Option<i32> {
+        return self.turn_limit;
+    }
+
+    /// Overrides the default starting squares, one for each player.
+    /// Each square has to be on an edge, the player's goal is the opposite edge.
+    pub fn set_starting_squares(mut self, squares: Vec<Point>) -> RuleSet {
+        self.starting_squares = squares;
+        return self;
+    }
+
+    /// Where each player starts. Unless set otherwise players start in the
+    /// middle of an edge going clockwise from the bottom, with two players
+    /// facing each other across the board.
+    pub fn starting_squares(&self) -> Vec<Point> {
+        if !self.starting_squares.is_empty() {
+            return self.starting_squares.clone();
+        }
+
+        let bottom = (self.width / 2, 0);
+        let left = (0, self.height / 2);
+        let top = (self.width / 2, self.height - 1);
+        let right = (self.width - 1, self.height / 2);
+        return match self.players {
+            1 => vec![bottom],
+            2 => vec![bottom, top],
+            3 => vec![bottom, left, top],
+            _ => vec![bottom, left, top, right],
+        };
+    }
+
+    /// The edge a square is on, top and bottom win over the sides at corners.
+    fn edge_of(&self, (x, y): Point) -> Option<Edge> {
+        if y == 0 {
+            return Some(Edge::Bottom);
+        }
+        if y == self.height - 1 {
+            return Some(Edge::Top);
+        }
+        if x == 0 {
+            return Some(Edge::Left);
+        }
+        if x == self.width - 1 {
+            return Some(Edge::Right);
+        }
+        return None;
+    }
+
+    /// Explains what is wrong with the rules, if anything.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width < 2 || self.height < 2 {
+            return Err(format!("{}x{} board is too small", self.width, self.height));
+        }
+        if !(1..=4).contains(&self.players) {
+            return Err(format!("{} players is not supported", self.players));
+        }
+        if self.walls_per_player < 0 {
+            return Err(String::from("walls per player can't be negative"));
+        }
+        if let Some(limit) = self.turn_limit {
+            if limit < 1 {
+                return Err(format!("turn limit of {} is too small", limit));
+            }
+        }
+
+        let squares = self.starting_squares();
+        if squares.len() != self.players as usize {
+            return Err(format!(
+                "{} starting squares for {} players",
+                squares.len(),
+                self.players
+            ));
+        }
+        for (i, square) in squares.iter().enumerate() {
+            if self.edge_of(*square).is_none() {
+                return Err(format!("starting square {:?} is not on an edge", square));
+            }
+            if squares[..i].contains(square) {
+                return Err(format!("two players start on {:?}", square));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Sets up the board at the start of a game.
+    /// Panics if the rules are not valid.
+    pub fn create_board(&self) -> Board {
+        if let Err(reason) = self.validate() {
+            panic!("Invalid rules: {}", reason);
+        }
+
+        let mut board = Board::create()
+            .set_width(self.width)
+            .set_height(self.height);
+        for square in self.starting_squares() {
+            let goal = Goal::Edge(self.edge_of(square).unwrap().opposite());
+            board = board.add_player(square, goal);
+        }
+        return board;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn standard_rules_match_default_board() {
+        assert_eq!(
+            Board::create_default(),
+            RuleSet::standard_two_player().create_board()
+        );
+        assert_eq!(
+            Board::create_four_player(),
+            RuleSet::standard_four_player().create_board()
+        );
+    }
+
+    #[test]
+    fn small_board_starting_squares() {
+        let rules = RuleSet::standard_two_player().set_width(5).set_height(5);
+        assert_eq!(vec![(2, 0), (2, 4)], rules.starting_squares());
+        let board = rules.create_board();
+        assert!(board.is_goal(0, (0, 4)));
+        assert!(board.is_goal(1, (0, 0)));
+    }
+
+    #[test]
+    fn custom_starting_squares() {
+        let board = RuleSet::standard_two_player()
+            .set_starting_squares(vec![(0, 3), (8, 5)])
+            .create_board();
+        assert_eq!((0, 3), board.get_pawn(0).unwrap());
+        assert!(board.is_goal(0, (8, 0)));
+        assert!(board.is_goal(1, (0, 8)));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let rules = RuleSet::standard_two_player();
+        assert!(rules.validate().is_ok());
+        assert!(rules.clone().set_width(1).validate().is_err());
+        assert!(rules.clone().set_players(5).validate().is_err());
+        assert!(rules.clone().set_walls_per_player(-1).validate().is_err());
+        assert!(rules.clone().set_turn_limit(Some(0)).validate().is_err());
+        assert!(rules
+            .clone()
+            .set_starting_squares(vec![(4, 0)])
+            .validate()
+            .is_err());
+        assert!(rules
+            .clone()
+            .set_starting_squares(vec![(4, 0), (4, 4)])
+            .validate()
+            .is_err());
+        assert!(rules
+            .set_starting_squares(vec![(4, 0), (4, 0)])
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,321 @@
+#![allow(dead_code)]
+
+//! Saving games and reading them back.
+//!
+//! Games are saved as a game record, see `record`, which keeps the rules,
+//! result, undone turns and draw offers as well as the history.
+//!
+//! Older saves written by `Quoridor::history_to_str` can still be read. They
+//! are an optional `Players-<n>` line followed by one turn per line: `M-Up`
+//! for a move, `J-Up-Left` for a diagonal jump, `P-4,2-Horizontal` for a
+//! wall and `Pass`. Saves without the players line are two player games.
+
+use crate::board::{Direction, Orientation, Point};
+use crate::game::{Quoridor, Turn};
+use crate::record::{read_records, GameRecord};
+use crate::rules::RuleSet;
+use std::fmt;
+use std::io::Write;
+
+/// Why a save couldn't be read, with the line it went wrong on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadError {
+    /// Counting from 1, 0 if the file itself couldn't be read
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.reason);
+        }
+        return write!(f, "line {}: {}", self.line, self.reason);
+    }
+}
+
+fn parse_direction(text: &str) -> Result<Direction, String> {
+    return match text {
+        "Up" => Ok(Direction::Up),
+        "Down" => Ok(Direction::Down),
+        "Left" => Ok(Direction::Left),
+        "Right" => Ok(Direction::Right),
+        _ => Err(format!("\"{}\" is not a direction", text)),
+    };
+}
+
+fn parse_orientation(text: &str) -> Result<Orientation, String> {
+    return match text {
+        "Horizontal" => Ok(Orientation::Horizontal),
+        "Vertical" => Ok(Orientation::Vertical),
+        _ => Err(format!("\"{}\" is not an orientation", text)),
+    };
+}
+
+fn parse_point(text: &str) -> Result<Point, String> {
+    let not_a_point = || format!("\"{}\" is not a square", text);
+    let (x, y) = text.split_once(',').ok_or_else(not_a_point)?;
+    let x = x.parse().map_err(|_| not_a_point())?;
+    let y = y.parse().map_err(|_| not_a_point())?;
+    return Ok((x, y));
+}
+
+/// Reads a single line of a save, like `J-Up-Left`.
+pub fn parse_turn(line: &str) -> Result<Turn, String> {
+    let parts: Vec<&str> = line.split('-').collect();
+    return match parts.as_slice() {
+        ["Pass"] => Ok(Turn::Pass),
+        ["M", direction] => Ok(Turn::MovePawn(parse_direction(direction)?)),
+        ["J", direction, side] => Ok(Turn::JumpPawn(
+            parse_direction(direction)?,
+            parse_direction(side)?,
+        )),
+        ["P", point, orientation] => Ok(Turn::PlaceWall(
+            parse_point(point)?,
+            parse_orientation(orientation)?,
+        )),
+        _ => Err(format!("\"{}\" is not a turn", line)),
+    };
+}
+
+/// Replays a save into a new game. Blank lines are skipped, anything else
+/// that isn't a legal turn stops the load.
+pub fn read_history(text: &str) -> Result<Quoridor, LoadError> {
+    let mut lines = text.lines().enumerate().peekable();
+    let mut rules = RuleSet::standard_two_player();
+    if let Some((number, header)) = lines.peek().copied() {
+        if let Some(players) = header.trim().strip_prefix("Players-") {
+            rules = match players {
+                "2" => RuleSet::standard_two_player(),
+                "4" => RuleSet::standard_four_player(),
+                _ => {
+                    return Err(LoadError {
+                        line: number + 1,
+                        reason: format!("{} players is not supported", players),
+                    })
+                }
+            };
+            lines.next();
+        }
+    }
+
+    let mut game = Quoridor::new(rules);
+    for (number, line) in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |reason| LoadError {
+            line: number + 1,
+            reason,
+        };
+        let turn = parse_turn(line).map_err(error)?;
+        game.play(turn).map_err(|reason| error(reason.to_string()))?;
+    }
+    return Ok(game);
+}
+
+/// Reads a save in either format.
+pub fn read_save(text: &str) -> Result<Quoridor, LoadError> {
+    if !text.trim_start().starts_with('[') {
+        return read_history(text);
+    }
+    let record = read_records(text)?.into_iter().next().ok_or(LoadError {
+        line: 0,
+        reason: String::from("there is no game in the save"),
+    })?;
+    return record
+        .to_game()
+        .map_err(|reason| LoadError { line: 0, reason });
+}
+
+/// Reads a save from a file.
+pub fn load_file(path: &str) -> Result<Quoridor, LoadError> {
+    let text = std::fs::read_to_string(path).map_err(|reason| LoadError {
+        line: 0,
+        reason: format!("couldn't read {}: {}", path, reason),
+    })?;
+    return read_save(&text);
+}
+
+/// Saves a game to a file without ever leaving a half written save behind.
+///
+/// The game is written to a temporary file next to `path` first, which then
+/// replaces the old save in one go. If anything goes wrong the old save is
+/// left as it was.
+pub fn save_file(game: &Quoridor, path: &str) -> std::io::Result<()> {
+    let temporary = format!("{}.tmp", path);
+    let result = std::fs::File::create(&temporary).and_then(|mut file| {
+        file.write_all(GameRecord::from_game(game).to_string().as_bytes())?;
+        return file.sync_all();
+    });
+    if let Err(reason) = result.and_then(|_| std::fs::rename(&temporary, path)) {
+        std::fs::remove_file(&temporary).ok();
+        return Err(reason);
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::Direction::*;
+
+    #[test]
+    fn parse_each_kind_of_turn() {
+        assert_eq!(Ok(Turn::MovePawn(Up)), parse_turn("M-Up"));
+        assert_eq!(Ok(Turn::JumpPawn(Down, Left)), parse_turn("J-Down-Left"));
+        assert_eq!(
+            Ok(Turn::PlaceWall((4, 2), Orientation::Horizontal)),
+            parse_turn("P-4,2-Horizontal")
+        );
+        assert!(parse_turn("M-Sideways").is_err());
+        assert!(parse_turn("P-4-Vertical").is_err());
+        assert!(parse_turn("X-Up").is_err());
+        assert!(parse_turn("").is_err());
+    }
+
+    #[test]
+    fn saved_game_loads_back() {
+        let mut game = Quoridor::new_two_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        game.play(Turn::MovePawn(Left)).unwrap();
+
+        let loaded = read_history(&game.history_to_str()).unwrap();
+        assert_eq!(game.history_to_str(), loaded.history_to_str());
+        assert_eq!(game.to_string(), loaded.to_string());
+        assert_eq!(game.current_player(), loaded.current_player());
+        assert_eq!(9, loaded.walls_left(1));
+    }
+
+    #[test]
+    fn four_player_game_loads_back() {
+        let mut game = Quoridor::new_four_player();
+        for _ in 0..5 {
+            let turn = game.legal_turns().next().unwrap();
+            game.play(turn).unwrap();
+        }
+        let loaded = read_history(&game.history_to_str()).unwrap();
+        assert_eq!(4, loaded.number_of_players());
+        assert_eq!(game.to_string(), loaded.to_string());
+    }
+
+    #[test]
+    fn saves_without_players_are_two_player() {
+        let game = read_history("M-Up\n\nM-Down\n").unwrap();
+        assert_eq!(2, game.number_of_players());
+        assert_eq!(0, game.current_player());
+    }
+
+    #[test]
+    fn errors_give_line_numbers() {
+        let error = read_history("Players-2\nM-Up\nM-Nowhere\n").unwrap_err();
+        assert_eq!(3, error.line);
+        assert_eq!("line 3: \"Nowhere\" is not a direction", error.to_string());
+
+        // Player 2 can't move off the top of the board
+        let error = read_history("M-Up\nM-Up\n").unwrap_err();
+        assert_eq!(2, error.line);
+        assert_eq!("line 2: that would be off the board", error.to_string());
+
+        let error = read_history("Players-3\n").unwrap_err();
+        assert_eq!(1, error.line);
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let directory = std::env::temp_dir().join(format!("quoridor-save-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("game.sav");
+        let path = path.to_str().unwrap();
+
+        let mut game = Quoridor::new_four_player();
+        game.play(Turn::MovePawn(Up)).unwrap();
+        game.play(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+        game.play(Turn::MovePawn(Down)).unwrap();
+        game.undo();
+        save_file(&game, path).unwrap();
+        // Saving again replaces the old save
+        game.offer_draw(0).unwrap();
+        save_file(&game, path).unwrap();
+
+        let loaded = load_file(path).unwrap();
+        assert_eq!(game.to_string(), loaded.to_string());
+        assert_eq!(game.undone(), loaded.undone());
+        assert_eq!(game.draw_offers(), loaded.draw_offers());
+        assert!(!directory.join("game.sav.tmp").exists());
+
+        // A save that can't be written leaves the old one alone
+        std::fs::create_dir_all(directory.join("blocked.sav")).unwrap();
+        let blocked = directory.join("blocked.sav");
+        assert!(save_file(&game, blocked.to_str().unwrap()).is_err());
+        assert!(blocked.is_dir());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn save_and_load_custom_rules() {
+        let directory = std::env::temp_dir().join(format!("quoridor-rules-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("game.sav");
+        let path = path.to_str().unwrap();
+
+        let three_player = RuleSet::standard_two_player()
+            .set_players(3)
+            .set_starting_squares(vec![(0, 3), (8, 4), (4, 8)]);
+        let big_board = RuleSet::standard_two_player()
+            .set_width(11)
+            .set_height(11)
+            .set_walls_per_player(15)
+            .set_turn_limit(Some(200));
+        for rules in [three_player, big_board].iter() {
+            let mut game = Quoridor::new(rules.clone());
+            game.play(Turn::MovePawn(Right)).unwrap();
+            game.play(Turn::PlaceWall((4, 2), Orientation::Horizontal)).unwrap();
+            save_file(&game, path).unwrap();
+
+            let loaded = load_file(path).unwrap();
+            assert_eq!(rules, loaded.rules());
+            assert_eq!(game.to_position_string(), loaded.to_position_string());
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn save_and_load_from_a_position() {
+        let directory = std::env::temp_dir().join(format!("quoridor-start-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("game.sav");
+        let path = path.to_str().unwrap();
+
+        let start = "9x9 e5,e6 e3h 9,10 2";
+        let mut game = Quoridor::from_position_string(start).unwrap();
+        game.play(Turn::PlaceWall((3, 5), Orientation::Horizontal)).unwrap();
+        game.play(Turn::MovePawn(Left)).unwrap();
+        game.play(Turn::MovePawn(Right)).unwrap();
+        game.undo();
+        save_file(&game, path).unwrap();
+
+        let mut loaded = load_file(path).unwrap();
+        assert_eq!(game.to_position_string(), loaded.to_position_string());
+        assert_eq!(game.history(), loaded.history());
+        assert_eq!(start, loaded.rewound().to_position_string());
+        assert_eq!(Some(Turn::MovePawn(Right)), loaded.redo());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn old_saves_still_load() {
+        let game = read_save("Players-4\nM-Up\n").unwrap();
+        assert_eq!(4, game.number_of_players());
+        assert_eq!(1, game.history().len());
+        assert!(read_save("").unwrap().history().is_empty());
+    }
+
+    #[test]
+    fn missing_file() {
+        let error = load_file("no/such/file.sav").unwrap_err();
+        assert_eq!(0, error.line);
+    }
+}
@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+
+//! Keys for Zobrist hashing of positions.
+//!
+//! A position's hash is every key that applies to it xored together, so
+//! moving a pawn or placing a wall only needs a couple of keys xored in or
+//! out. Keys are worked out from what they stand for with splitmix64 rather
+//! than looked up, so they work for any board size and never change between
+//! runs, which lets hashes be stored.
+
+use crate::board::{Orientation, Point};
+
+/// The splitmix64 mixing function.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
+
+/// A key for a kind of feature with up to three small numbers describing it.
+fn key(kind: u64, a: i32, b: i32, c: i32) -> u64 {
+    let field = |n: i32| n as u16 as u64;
+    return mix(kind << 48 | field(a) << 32 | field(b) << 16 | field(c));
+}
+
+/// For a pawn standing on a square.
+pub fn pawn(pawn: i8, (x, y): Point) -> u64 {
+    return key(1, pawn as i32, x, y);
+}
+
+/// For a wall on the board.
+pub fn wall((x, y): Point, orientation: Orientation) -> u64 {
+    let kind = match orientation {
+        Orientation::Horizontal => 2,
+        Orientation::Vertical => 3,
+    };
+    return key(kind, 0, x, y);
+}
+
+/// For a player having a number of walls left.
+pub fn walls_left(player: i32, walls: i32) -> u64 {
+    return key(4, player, walls, 0);
+}
+
+/// For it being a player's turn.
+pub fn side_to_move(player: i32) -> u64 {
+    return key(5, player, 0, 0);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn keys_are_stable() {
+        assert_eq!(0xE220_A839_7B1D_CDAF, mix(0));
+        assert_eq!(pawn(0, (4, 0)), pawn(0, (4, 0)));
+    }
+
+    #[test]
+    fn keys_are_distinct() {
+        let mut keys = HashSet::new();
+        for x in -1..11 {
+            for y in -1..11 {
+                for player in 0..4 {
+                    assert!(keys.insert(pawn(player, (x, y))));
+                }
+                assert!(keys.insert(wall((x, y), Orientation::Horizontal)));
+                assert!(keys.insert(wall((x, y), Orientation::Vertical)));
+            }
+        }
+        for player in 0..4 {
+            for walls in 0..=20 {
+                assert!(keys.insert(walls_left(player, walls)));
+            }
+            assert!(keys.insert(side_to_move(player)));
+        }
+    }
+}